@@ -1,6 +1,9 @@
 use super::game::Game;
 use super::game_hard::GameHard;
 use super::game_online::GameOnline;
+use super::game_practice::GamePractice;
+use super::game_puzzle::GamePuzzle;
+use super::spectate::Spectate;
 use crate::game::{HEIGHT, WIDTH};
 use yew::prelude::*;
 
@@ -22,6 +25,9 @@ enum GameMode {
     Normal,
     Hard,
     Online,
+    Practice,
+    Puzzle,
+    Spectate,
 }
 
 #[function_component(App)]
@@ -52,6 +58,9 @@ pub fn app() -> Html {
                 <h3><a href="#" onclick={select_difficulty(GameMode::Normal)}>{"NORMAL"}</a></h3>
                 <h3><a href="#" onclick={select_difficulty(GameMode::Hard)}>{"MASTER"}</a></h3>
                 <h3><a href="#" onclick={select_difficulty(GameMode::Online)}>{"ONLINE"}</a></h3>
+                <h3><a href="#" onclick={select_difficulty(GameMode::Practice)}>{"PRACTICE"}</a></h3>
+                <h3><a href="#" onclick={select_difficulty(GameMode::Puzzle)}>{"PUZZLE"}</a></h3>
+                <h3><a href="#" onclick={select_difficulty(GameMode::Spectate)}>{"SPECTATE"}</a></h3>
             </div>
         },
         Some(GameMode::Normal) => html! {
@@ -63,5 +72,14 @@ pub fn app() -> Html {
         Some(GameMode::Online) => html! {
             <GameOnline cell_size={cell_size} />
         },
+        Some(GameMode::Practice) => html! {
+            <GamePractice cell_size={cell_size} />
+        },
+        Some(GameMode::Puzzle) => html! {
+            <GamePuzzle cell_size={cell_size} />
+        },
+        Some(GameMode::Spectate) => html! {
+            <Spectate cell_size={cell_size} />
+        },
     }
 }
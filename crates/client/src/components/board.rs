@@ -4,14 +4,18 @@ use crate::game::{FloatingCell, FloatingParticle};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
-pub struct Props<const WIDTH: usize, const HEIGHT: usize> {
+pub struct Props {
+    pub width: usize,
+    pub height: usize,
     pub floating_cells: Vec<FloatingCell>,
     pub particles: Vec<FloatingParticle>,
 }
 
 #[function_component(Board)]
-pub fn board<const WIDTH: usize, const HEIGHT: usize>(props: &Props<WIDTH, HEIGHT>) -> Html {
+pub fn board(props: &Props) -> Html {
     let Props {
+        width,
+        height,
         floating_cells,
         particles,
     } = props;
@@ -37,7 +41,7 @@ pub fn board<const WIDTH: usize, const HEIGHT: usize>(props: &Props<WIDTH, HEIGH
 
     html! {
         <>
-            <rect width={WIDTH.to_string()} height={HEIGHT.to_string()} class="stroke" />
+            <rect width={width.to_string()} height={height.to_string()} class="stroke" />
             {for cells}
             {for particles}
         </>
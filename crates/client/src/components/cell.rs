@@ -33,5 +33,36 @@ pub fn cell(props: &Props) -> Html {
                 <rect x={x} y={y} width="1" height="1" opacity={opacity} class="stroke" />
             }
         }
+        CellType::Armored => {
+            let inner_x = (x + 0.2).to_string();
+            let inner_y = (y + 0.2).to_string();
+            let x = x.to_string();
+            let y = y.to_string();
+            html! {
+                <g opacity={opacity}>
+                    <rect x={x} y={y} width="1" height="1" class="stroke" />
+                    <rect x={inner_x} y={inner_y} width="0.6" height="0.6" class="stroke" />
+                </g>
+            }
+        }
+        CellType::RowBomb | CellType::ColumnBomb | CellType::CrossBomb => {
+            let cx = (x + 0.5).to_string();
+            let cy = (y + 0.5).to_string();
+            let bar_x = (x + 0.1).to_string();
+            let bar_y = (y + 0.42).to_string();
+            let pole_x = (x + 0.42).to_string();
+            let pole_y = (y + 0.1).to_string();
+            html! {
+                <g opacity={opacity}>
+                    <circle cx={cx} cy={cy} r="0.5" class="fill" />
+                    if cell_type != CellType::ColumnBomb {
+                        <rect x={bar_x} y={bar_y} width="0.8" height="0.16" fill="#000" />
+                    }
+                    if cell_type != CellType::RowBomb {
+                        <rect x={pole_x} y={pole_y} width="0.16" height="0.8" fill="#000" />
+                    }
+                </g>
+            }
+        }
     }
 }
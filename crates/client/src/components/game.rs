@@ -1,6 +1,10 @@
 use super::board::Board;
 use super::button::Button;
 use crate::game::{self, *};
+use common::board::{DynamicBoard, Grid, Move};
+use common::grade::score_rank;
+use common::puzzle::Status;
+use common::scoring::ScoringRule;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::{
@@ -11,20 +15,7 @@ use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use yew::prelude::*;
 
-const RANKS: [(usize, &str); 10] = [
-    (0, "D"),
-    (5000, "D+"),
-    (10000, "C"),
-    (15000, "C+"),
-    (20000, "B"),
-    (25000, "B+"),
-    (30000, "A"),
-    (35000, "A+"),
-    (40000, "A++"),
-    (50000, "Awesome"),
-];
-
-fn raf_loop(mut func: impl FnMut() + 'static) {
+pub(super) fn raf_loop(mut func: impl FnMut() + 'static) {
     let f = Rc::new(RefCell::new(None));
     let g = f.clone();
 
@@ -62,7 +53,7 @@ impl Audio {
     }
 }
 
-struct LazyAudio {
+pub(super) struct LazyAudio {
     context: Rc<web_sys::AudioContext>,
     src: String,
     audio: Arc<Mutex<Option<Audio>>>,
@@ -77,7 +68,7 @@ async fn resolve_promise<T: From<JsValue>>(promise: js_sys::Promise) -> T {
 }
 
 impl LazyAudio {
-    fn new(src: &str, context: Rc<web_sys::AudioContext>) -> LazyAudio {
+    pub(super) fn new(src: &str, context: Rc<web_sys::AudioContext>) -> LazyAudio {
         LazyAudio {
             context,
             src: src.to_string(),
@@ -86,7 +77,7 @@ impl LazyAudio {
         }
     }
 
-    async fn load(&self) {
+    pub(super) async fn load(&self) {
         let mut audio = self.audio.lock().unwrap();
         if audio.is_none() {
             let window = web_sys::window().unwrap();
@@ -101,7 +92,7 @@ impl LazyAudio {
         self.is_loaded.store(true, Relaxed);
     }
 
-    async fn play(&self) {
+    pub(super) async fn play(&self) {
         if self.is_loaded.load(Relaxed) {
             let audio = self.audio.lock().unwrap();
             audio.as_ref().unwrap().play();
@@ -125,142 +116,252 @@ pub fn preview(props: &PreviewProps) -> Html {
     }
 }
 
-#[derive(Clone, PartialEq, Properties)]
-pub struct Props {
-    pub cell_size: f64,
-}
-
-#[function_component(Game)]
-pub fn game_component(props: &Props) -> Html {
-    let Props { cell_size } = props.clone();
-    let use_sound = |src: &str, context: &Rc<web_sys::AudioContext>| {
-        let cloned_context = context.clone();
-        let sound = use_ref(|| LazyAudio::new(src, cloned_context));
-        let cloned_sound = sound.clone();
-        use_effect_with_deps(
-            move |_| {
-                wasm_bindgen_futures::spawn_local(async move { cloned_sound.load().await });
-                || ()
-            },
-            (),
-        );
-        sound
-    };
-
-    let remove_preview = use_state(Vec::new);
-    let board_ref = use_node_ref();
-    let game = use_reducer(game::Game::new);
-    let audio_context = use_ref(|| web_sys::AudioContext::new().unwrap());
-    let break_sound = use_sound("/sound/break.wav", &audio_context);
-    let fall_sound = use_sound("/sound/fall.wav", &audio_context);
-    let feed_sound = use_sound("/sound/feed.wav", &audio_context);
-    let stuck_sound = use_sound("/sound/stuck.wav", &audio_context);
-
-    let cloned_game = game.clone();
-
+fn use_sound(src: &str, context: &Rc<web_sys::AudioContext>) -> Rc<LazyAudio> {
+    let cloned_context = context.clone();
+    let sound = use_ref(|| LazyAudio::new(src, cloned_context));
+    let cloned_sound = sound.clone();
     use_effect_with_deps(
         move |_| {
-            game.dispatch(GameAction::Feed);
-            raf_loop(move || game.dispatch(GameAction::Animate));
+            wasm_bindgen_futures::spawn_local(async move { cloned_sound.load().await });
             || ()
         },
         (),
     );
+    sound
+}
 
-    let window = web_sys::window().unwrap();
-    let game = cloned_game;
-    let position = board_ref.cast::<web_sys::Element>().map(|board| {
+/// The sounds a board's animations make.
+pub(super) struct BoardSounds {
+    break_sound: Rc<LazyAudio>,
+    fall_sound: Rc<LazyAudio>,
+    feed_sound: Rc<LazyAudio>,
+    stuck_sound: Rc<LazyAudio>,
+}
+
+impl BoardSounds {
+    /// Plays the first of the sounds an animation frame made.
+    pub(super) fn play(&self, sounds: &[Sound]) {
+        if let Some(sound) = sounds.first() {
+            let sound = match sound {
+                Sound::Break => &self.break_sound,
+                Sound::Feed => &self.feed_sound,
+                Sound::Fall => &self.fall_sound,
+                Sound::Stuck => &self.stuck_sound,
+                _ => unreachable!(),
+            }
+            .clone();
+
+            wasm_bindgen_futures::spawn_local(async move { sound.play().await });
+        }
+    }
+}
+
+pub(super) fn use_board_sounds() -> BoardSounds {
+    let audio_context = use_ref(|| web_sys::AudioContext::new().unwrap());
+    BoardSounds {
+        break_sound: use_sound("/sound/break.wav", &audio_context),
+        fall_sound: use_sound("/sound/fall.wav", &audio_context),
+        feed_sound: use_sound("/sound/feed.wav", &audio_context),
+        stuck_sound: use_sound("/sound/stuck.wav", &audio_context),
+    }
+}
+
+/// A game played by detonating the cells of one board.
+pub(super) trait BoardGame: Reducible + 'static {
+    fn board(&self) -> &AnimatedBoard;
+
+    /// Whether the board still takes moves.
+    fn is_playing(&self) -> bool;
+
+    fn remove(x: usize, y: usize) -> Self::Action;
+}
+
+impl BoardGame for game::Game {
+    fn board(&self) -> &AnimatedBoard {
+        &self.board
+    }
+
+    fn is_playing(&self) -> bool {
+        !self.is_over()
+    }
+
+    fn remove(x: usize, y: usize) -> GameAction {
+        GameAction::Remove(x, y)
+    }
+}
+
+impl BoardGame for GamePractice {
+    fn board(&self) -> &AnimatedBoard {
+        &self.board
+    }
+
+    fn is_playing(&self) -> bool {
+        !self.is_over()
+    }
+
+    fn remove(x: usize, y: usize) -> PracticeGameAction {
+        PracticeGameAction::Remove(x, y)
+    }
+}
+
+impl BoardGame for GamePuzzle {
+    fn board(&self) -> &AnimatedBoard {
+        &self.board
+    }
+
+    fn is_playing(&self) -> bool {
+        self.status() == Status::Playing
+    }
+
+    fn remove(x: usize, y: usize) -> PuzzleGameAction {
+        PuzzleGameAction::Remove(x, y)
+    }
+}
+
+/// The handlers to put on a board's `<svg>`, and the cells the move under the mouse removes.
+pub(super) struct BoardInput {
+    pub(super) node_ref: NodeRef,
+    pub(super) onmousedown: Callback<web_sys::MouseEvent>,
+    pub(super) ontouchstart: Callback<web_sys::TouchEvent>,
+    pub(super) onmousemove: Callback<web_sys::MouseEvent>,
+    preview: UseStateHandle<Option<Move>>,
+}
+
+impl BoardInput {
+    /// Points `rule` gives the move under the mouse on `board`, scored as if it were the first
+    /// chain of the game.
+    pub(super) fn preview_score(
+        &self,
+        board: &DynamicBoard,
+        mut rule: Box<dyn ScoringRule>,
+    ) -> usize {
+        self.preview.as_ref().map_or(0, |m| {
+            let report = board.clone().remove(m.x, m.y);
+            rule.score(&report, 0)
+        })
+    }
+
+    pub(super) fn preview(&self) -> Html {
+        self.preview
+            .iter()
+            .flat_map(|m| &m.removed)
+            .map(|&(x, y)| html! { <Preview x={x as f64} y={y as f64} /> })
+            .collect()
+    }
+}
+
+/// Turns clicks, or taps on touch screens, on a `width` by `height` board drawn `cell_size`
+/// pixels a cell into removes.
+pub(super) fn use_board_input<G: BoardGame>(
+    game: &UseReducerHandle<G>,
+    cell_size: f64,
+    width: usize,
+    height: usize,
+) -> BoardInput {
+    let preview = use_state(|| None);
+    let node_ref = use_node_ref();
+    let position = node_ref.cast::<web_sys::Element>().map(|board| {
         let rect = board.get_bounding_client_rect();
         (rect.x(), rect.y())
     });
+    let cell_at = move |client_x: i32, client_y: i32| {
+        let (left, top) = position.unwrap();
+        let x = ((client_x as f64 - left) / cell_size)
+            .max(0.)
+            .min(width as f64 - 1.) as usize;
+        let y = ((client_y as f64 - top) / cell_size)
+            .max(0.)
+            .min(height as f64 - 1.) as usize;
+        (x, y)
+    };
 
     let cloned_game = game.clone();
-    let cloned_remove_preview = remove_preview.clone();
+    let cloned_preview = preview.clone();
     let onmousemove = Callback::from(move |event: web_sys::MouseEvent| {
-        let (left, top) = position.unwrap();
-        if cloned_game.is_over() || cloned_game.board.is_animating() {
-            cloned_remove_preview.set(Vec::new());
+        if !cloned_game.is_playing() || cloned_game.board().is_animating() {
+            cloned_preview.set(None);
             return;
         }
-        let x = ((event.client_x() as f64 - left) / cell_size)
-            .max(0.)
-            .min(WIDTH as f64 - 1.) as usize;
-        let y = ((event.client_y() as f64 - top) / cell_size)
-            .max(0.)
-            .min(HEIGHT as f64 - 1.) as usize;
-        let mut board = cloned_game.board.board.clone();
-        let removed_cells = board.remove(x, y);
-        let preview = removed_cells
-            .into_iter()
-            .map(|(_, _, x, y, _)| (x as f64, y as f64))
-            .collect();
-        cloned_remove_preview.set(preview);
+        let (x, y) = cell_at(event.client_x(), event.client_y());
+        cloned_preview.set(cloned_game.board().move_at(x, y).cloned());
     });
 
     let cloned_game = game.clone();
-    let cloned_remove_preview = remove_preview.clone();
+    let cloned_preview = preview.clone();
     let onmousedown = Callback::from(move |event: web_sys::MouseEvent| {
-        let (left, top) = position.unwrap();
-        cloned_remove_preview.set(Vec::new());
+        cloned_preview.set(None);
         event.prevent_default();
-        let x = ((event.client_x() as f64 - left) / cell_size)
-            .max(0.)
-            .min(WIDTH as f64 - 1.) as usize;
-        let y = ((event.client_y() as f64 - top) / cell_size)
-            .max(0.)
-            .min(HEIGHT as f64 - 1.) as usize;
-        cloned_game.dispatch(GameAction::Remove(x, y));
+        let (x, y) = cell_at(event.client_x(), event.client_y());
+        cloned_game.dispatch(G::remove(x, y));
     });
 
     let cloned_game = game.clone();
-    let cloned_remove_preview = remove_preview.clone();
+    let cloned_preview = preview.clone();
     let ontouchstart = Callback::from(move |event: web_sys::TouchEvent| {
-        cloned_remove_preview.set(Vec::new());
-        let (left, top) = position.unwrap();
+        cloned_preview.set(None);
         let touches = event.target_touches();
         for i in 0..touches.length() {
             if let Some(event) = touches.item(i) {
-                let x = ((event.client_x() as f64 - left) / cell_size)
-                    .max(0.)
-                    .min(WIDTH as f64 - 1.) as usize;
-                let y = ((event.client_y() as f64 - top) / cell_size)
-                    .max(0.)
-                    .min(HEIGHT as f64 - 1.) as usize;
-                cloned_game.dispatch(GameAction::Remove(x, y));
+                let (x, y) = cell_at(event.client_x(), event.client_y());
+                cloned_game.dispatch(G::remove(x, y));
             }
         }
     });
 
+    let window = web_sys::window().unwrap();
+    let (onmousedown, ontouchstart) = if window.navigator().max_touch_points() > 0 {
+        (Callback::from(|_| ()), ontouchstart)
+    } else {
+        (onmousedown, Callback::from(|_| ()))
+    };
+
+    BoardInput {
+        node_ref,
+        onmousedown,
+        ontouchstart,
+        onmousemove,
+        preview,
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub cell_size: f64,
+}
+
+#[function_component(Game)]
+pub fn game_component(props: &Props) -> Html {
+    let Props { cell_size } = props.clone();
+
+    let game = use_reducer(game::Game::new);
+    let sounds = use_board_sounds();
+    let input = use_board_input(&game, cell_size, WIDTH, HEIGHT);
+
     let cloned_game = game.clone();
-    let onclick = Callback::from(move |_| cloned_game.dispatch(GameAction::Retry));
 
-    let (floating_cells, sounds) = game.board.frame();
+    use_effect_with_deps(
+        move |_| {
+            game.dispatch(GameAction::Feed);
+            raf_loop(move || game.dispatch(GameAction::Animate));
+            || ()
+        },
+        (),
+    );
+
+    let game = cloned_game;
+
+    let cloned_game = game.clone();
+    let onclick = Callback::from(move |_| cloned_game.dispatch(GameAction::Retry));
 
-    if let Some(sound) = sounds.first() {
-        let sound = match sound {
-            Sound::Break => break_sound,
-            Sound::Feed => feed_sound,
-            Sound::Fall => fall_sound,
-            Sound::Stuck => stuck_sound,
-            _ => unreachable!(),
-        };
+    let cloned_game = game.clone();
+    let onsave = Callback::from(move |_| crate::replay::download(&cloned_game.replay()));
 
-        wasm_bindgen_futures::spawn_local(async move { sound.play().await });
-    }
+    let (floating_cells, frame_sounds) = game.board.frame();
+    sounds.play(&frame_sounds);
 
     let particles = game.board.particles();
     let score = game.score_animator.borrow().frame();
-    let rank_index = RANKS
-        .binary_search_by_key(&(game.score + 1), |x| x.0)
-        .unwrap_or_else(|x| x)
-        - 1;
-    let rank = RANKS[rank_index].1;
-
-    let (onmousedown, ontouchstart) = if window.navigator().max_touch_points() > 0 {
-        (Callback::from(|_| ()), ontouchstart)
-    } else {
-        (onmousedown, Callback::from(|_| ()))
-    };
+    let rank = score_rank(game.score());
 
     let width = WIDTH.to_string();
     let height = HEIGHT.to_string();
@@ -268,23 +369,20 @@ pub fn game_component(props: &Props) -> Html {
     let center_y = (HEIGHT as f64 / 2.).to_string();
     let upper_y = (HEIGHT as f64 / 3.).to_string();
 
-    let preview = remove_preview.iter().map(|&(x, y)| {
-        html! {
-            <Preview x={x} y={y} />
-        }
-    });
-    let score_preview = (remove_preview.len() + 1) * remove_preview.len() / 2;
+    let score_preview = input.preview_score(&game.board.board, game.scoring_rule());
 
     html! {
-        <svg style={format!("transform: scale({cell_size});")} width={width.clone()} height={height.clone()} onmousedown={onmousedown} ontouchstart={ontouchstart} onmousemove={onmousemove} ref={board_ref}>
+        <svg style={format!("transform: scale({cell_size});")} width={width.clone()} height={height.clone()} onmousedown={input.onmousedown.clone()} ontouchstart={input.ontouchstart.clone()} onmousemove={input.onmousemove.clone()} ref={input.node_ref.clone()}>
             <text x={center_x.clone()} y={center_y.clone()} class="numerator" font-size="1px">
-                {format!("{:03}", game.bombs_removed.min(game.bombs_limit))}
+                {format!("{:03}", game.bombs_removed().min(game.bombs_limit()))}
             </text>
             <text x={center_x.clone()} y={center_y.clone()} class="denominator" font-size="1px">
-                {format!("{:03}", game.bombs_limit)}
+                {format!("{:03}", game.bombs_limit())}
             </text>
-            {for preview}
-            <Board<WIDTH, HEIGHT>
+            {input.preview()}
+            <Board
+                width={WIDTH}
+                height={HEIGHT}
                 floating_cells={floating_cells}
                 particles={particles} />
             if game.is_over() && !game.board.is_animating() {
@@ -295,6 +393,7 @@ pub fn game_component(props: &Props) -> Html {
                     <tspan font-size="1px">{rank}</tspan>
                 </text>
                 <Button x={WIDTH as f64 / 2.} y={HEIGHT as f64 / 3. * 2.} font_size="0.5px" onclick={onclick}>{"Retry"}</Button>
+                <Button x={WIDTH as f64 / 2.} y={HEIGHT as f64 / 3. * 2. + 1.} font_size="0.5px" onclick={onsave}>{"Save replay"}</Button>
             }
             <text x="0" y="0" class="text" font-size="0.5px">
                 <tspan>{format!("SCORE: {}", score)}</tspan>
@@ -182,6 +182,9 @@ pub fn game_component(props: &Props) -> Html {
     let cloned_game = game.clone();
     let onclick = Callback::from(move |_| cloned_game.dispatch(GameAction::Retry));
 
+    let cloned_game = game.clone();
+    let onsave = Callback::from(move |_| crate::replay::download(&cloned_game.replay()));
+
     let (floating_cells, mut sounds) = game.board.frame();
     sounds.append(&mut game.sounds());
 
@@ -213,9 +216,9 @@ pub fn game_component(props: &Props) -> Html {
     let center_y = (HEIGHT as f64 / 2.).to_string();
     let upper_y = (HEIGHT as f64 / 3.).to_string();
 
-    let until_single = (game.until_single + 1) as f64 / (game.single_frequency) as f64;
+    let until_single = (game.until_single() + 1) as f64 / (game.single_frequency()) as f64;
     let indicator_width = (WIDTH as f64 * until_single).to_string();
-    let indicator_color = if game.until_single == 0 {
+    let indicator_color = if game.until_single() == 0 {
         "#FF2222"
     } else {
         "#FFFFFF"
@@ -229,23 +232,26 @@ pub fn game_component(props: &Props) -> Html {
     html! {
         <svg style={format!("transform: scale({cell_size});")} width={width.clone()} height={height.clone()} onmousedown={onmousedown} ontouchstart={ontouchstart} ref={board_ref}>
             <text x={center_x.clone()} y={center_y.clone()} class="numerator" font-size="1px">
-                {format!("{:03}", game.level.min(game.level_limit))}
+                {format!("{:03}", game.level().min(game.level_limit()))}
             </text>
             <text x={center_x.clone()} y={center_y} class="denominator" font-size="1px">
-                {format!("{:03}", game.level_limit)}
+                {format!("{:03}", game.level_limit())}
             </text>
-            <Board<WIDTH, HEIGHT>
+            <Board
+                width={WIDTH}
+                height={HEIGHT}
                 floating_cells={floating_cells}
                 particles={particles} />
             if game.is_over() && !game.board.is_animating() {
                 <rect x="0" y="0" width={width.clone()} height={height} fill="rgba(0, 0, 0, 0.5)" />
                 <text x={center_x.clone()} y={upper_y.clone()} class="text-center" font-size="1px" dominant-baseline="hanging">{"GAME OVER"}</text>
                 <Button x={WIDTH as f64 / 2.} y={HEIGHT as f64 / 3. * 2.} font_size="0.5px" onclick={onclick}>{"Retry"}</Button>
+                <Button x={WIDTH as f64 / 2.} y={HEIGHT as f64 / 3. * 2. + 1.} font_size="0.5px" onclick={onsave}>{"Save replay"}</Button>
             }
             if !game.is_started {
                 <text x={center_x.clone()} y={upper_y} class="text-center" font-size="1px" dominant-baseline="hanging">{"READY"}</text>
             }
-            if game.single_frequency < 100 {
+            if game.single_frequency() < 100 {
                 <rect x="0" y="0" width={indicator_width} height="0.1" fill={indicator_color} />
             }
             <text x="0" y="1px" transform={format!("scale({grade_zoom_rate})")} class="grade">
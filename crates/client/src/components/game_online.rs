@@ -1,7 +1,11 @@
 use super::board::Board;
+use super::button::Button;
+use super::lobby::Lobby;
+use super::opponents::Opponents;
 use crate::game::{self, *};
 use crate::websocket::WebsocketBus;
-use common::board::CellType;
+use common::board::Grid;
+use common::garbage::Targeting;
 use common::model::{RequestMessage, ResponseMessage};
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -99,6 +103,9 @@ impl LazyAudio {
     }
 }
 
+/// How much smaller than the player's board the live opponent board is drawn.
+const OPPONENT_SCALE: f64 = 0.3;
+
 #[derive(Clone, PartialEq, Properties)]
 pub struct Props {
     pub cell_size: f64,
@@ -133,16 +140,67 @@ pub fn game_online(props: &Props) -> Html {
     let cloned_game = game.clone();
     let websocket = use_bridge::<WebsocketBus, _>(move |msg: ResponseMessage| {
         let action = match msg {
+            ResponseMessage::Ready {
+                player,
+                players,
+                width,
+                height,
+                ..
+            } => OnlineGameAction::Start {
+                player,
+                players,
+                width,
+                height,
+            },
+            ResponseMessage::Resumed {
+                player,
+                players,
+                boards,
+                score,
+                pending,
+            } => OnlineGameAction::Resume {
+                player,
+                players,
+                boards,
+                score,
+                pending,
+            },
+            // The match went on without the player, who can only queue again.
+            ResponseMessage::SessionExpired => OnlineGameAction::GameOver,
+            ResponseMessage::Room {
+                code,
+                players,
+                ready,
+            } => OnlineGameAction::Lobby(game::Lobby::Room {
+                code,
+                players,
+                ready,
+            }),
+            ResponseMessage::RoomUnavailable { code } => {
+                OnlineGameAction::Lobby(game::Lobby::Menu {
+                    unavailable: Some(code),
+                })
+            }
             ResponseMessage::Remove { x, y } => OnlineGameAction::Remove(x, y),
-            ResponseMessage::Feed { row } => {
-                if let Ok(row) = <[bool; WIDTH]>::try_from(row) {
-                    let row = row.map(|x| if x { CellType::Bomb } else { CellType::Tile });
-                    OnlineGameAction::Feed(row)
-                } else {
-                    return;
-                }
+            ResponseMessage::Feed { row } => OnlineGameAction::Feed(row),
+            ResponseMessage::Score { score } => OnlineGameAction::Score(score),
+            ResponseMessage::Garbage { pending } => OnlineGameAction::Garbage(pending),
+            ResponseMessage::Sent { attack, target } => OnlineGameAction::Sent(attack, target),
+            ResponseMessage::OpponentRemove { player, x, y } => {
+                OnlineGameAction::OpponentRemove(player, x, y)
             }
-            _ => return,
+            ResponseMessage::OpponentFeed { player, row } => {
+                OnlineGameAction::OpponentFeed(player, row)
+            }
+            ResponseMessage::Stack { player, heights } => OnlineGameAction::Stack(player, heights),
+            ResponseMessage::Placement { player, place } => {
+                OnlineGameAction::Placement(player, place)
+            }
+            ResponseMessage::Win => OnlineGameAction::Result(MatchResult::Win),
+            ResponseMessage::Lose => OnlineGameAction::Result(MatchResult::Lose),
+            ResponseMessage::Draw => OnlineGameAction::Result(MatchResult::Draw),
+            ResponseMessage::GameOver => OnlineGameAction::GameOver,
+            ResponseMessage::Games { .. } | ResponseMessage::Snapshot { .. } => return,
         };
         cloned_game.dispatch(action);
     });
@@ -153,8 +211,7 @@ pub fn game_online(props: &Props) -> Html {
     use_effect_with_deps(
         move |_| {
             raf_loop(move || game.dispatch(OnlineGameAction::Animate));
-            cloned_ws.send(RequestMessage::Join);
-            || ()
+            move || cloned_ws.send(RequestMessage::Leave)
         },
         (),
     );
@@ -167,16 +224,43 @@ pub fn game_online(props: &Props) -> Html {
     let window = web_sys::window().unwrap();
     let game = cloned_game;
 
+    if game.lobby != game::Lobby::Playing {
+        let send = |request: RequestMessage, lobby: Option<game::Lobby>| {
+            let cloned_ws = websocket.clone();
+            let cloned_game = game.clone();
+            Callback::from(move |_| {
+                cloned_ws.send(request.clone());
+                if let Some(lobby) = lobby.clone() {
+                    cloned_game.dispatch(OnlineGameAction::Lobby(lobby));
+                }
+            })
+        };
+        let cloned_ws = websocket.clone();
+        let onjoin = Callback::from(move |code| cloned_ws.send(RequestMessage::JoinRoom { code }));
+        return html! {
+            <Lobby
+                lobby={game.lobby.clone()}
+                onqueue={send(RequestMessage::Join, Some(game::Lobby::Queued))}
+                oncreate={send(RequestMessage::CreateRoom, None)}
+                onjoin={onjoin}
+                onready={send(RequestMessage::Ready, None)}
+                onleave={send(RequestMessage::Leave, Some(game::Lobby::Menu { unavailable: None }))} />
+        };
+    }
+
+    let board_width = game.board.board.width();
+    let board_height = game.board.board.height();
+
     let cloned_ws = websocket.clone();
     let onmousedown = Callback::from(move |event: web_sys::MouseEvent| {
         event.prevent_default();
         let (left, top) = position.unwrap();
         let x = ((event.client_x() as f64 - left) / cell_size)
             .max(0.)
-            .min(WIDTH as f64 - 1.) as usize;
+            .min(board_width as f64 - 1.) as usize;
         let y = ((event.client_y() as f64 - top) / cell_size)
             .max(0.)
-            .min(HEIGHT as f64 - 1.) as usize;
+            .min(board_height as f64 - 1.) as usize;
         cloned_ws.send(RequestMessage::Remove{x, y});
     });
 
@@ -192,15 +276,60 @@ pub fn game_online(props: &Props) -> Html {
             if let Some(event) = touches.item(i) {
                 let x = ((event.client_x() as f64 - left) / cell_size)
                     .max(0.)
-                    .min(WIDTH as f64 - 1.) as usize;
+                    .min(board_width as f64 - 1.) as usize;
                 let y = ((event.client_y() as f64 - top) / cell_size)
                     .max(0.)
-                    .min(HEIGHT as f64 - 1.) as usize;
+                    .min(board_height as f64 - 1.) as usize;
                 cloned_ws.send(RequestMessage::Remove{x, y});
             }
         }
     });
 
+    // Tapping the targeting line cycles through the strategies instead of removing a cell.
+    let cloned_ws = websocket.clone();
+    let cloned_game = game.clone();
+    let next_targeting = move || {
+        let targeting = match cloned_game.targeting {
+            Targeting::Random => Targeting::Attackers,
+            Targeting::Attackers => Targeting::Lowest,
+            Targeting::Lowest => Targeting::Random,
+        };
+        cloned_ws.send(RequestMessage::Target { targeting });
+        cloned_game.dispatch(OnlineGameAction::Targeting(targeting));
+    };
+    let cloned_next_targeting = next_targeting.clone();
+    let ontargetmousedown = Callback::from(move |event: web_sys::MouseEvent| {
+        event.prevent_default();
+        event.stop_propagation();
+        cloned_next_targeting();
+    });
+    let ontargettouchstart = Callback::from(move |event: web_sys::TouchEvent| {
+        event.prevent_default();
+        event.stop_propagation();
+        next_targeting();
+    });
+    let targeting = match game.targeting {
+        Targeting::Random => "RANDOM",
+        Targeting::Attackers => "ATTACKERS",
+        Targeting::Lowest => "LOWEST",
+    };
+
+    let cloned_ws = websocket.clone();
+    let cloned_game = game.clone();
+    let onrequeue = Callback::from(move |event: web_sys::MouseEvent| {
+        event.prevent_default();
+        cloned_ws.send(RequestMessage::Join);
+        cloned_game.dispatch(OnlineGameAction::Requeue);
+    });
+    let result = match game.result {
+        Some(MatchResult::Win) => Some("YOU WIN"),
+        Some(MatchResult::Lose) => Some("YOU LOSE"),
+        Some(MatchResult::Draw) => Some("DRAW"),
+        None if game.is_over => Some("GAME OVER"),
+        None => None,
+    };
+    let center_x = (board_width as f64 / 2.).to_string();
+
     let (floating_cells, sounds) = game.board.frame();
 
     for sound in sounds {
@@ -219,20 +348,71 @@ pub fn game_online(props: &Props) -> Html {
 
     let particles = game.board.particles();
 
+    // The opponent's sounds stay silent so they are not mistaken for the player's own.
+    let opponent_view = game
+        .shown_opponent()
+        .and_then(|id| game.opponent_boards.get(&id))
+        .map(|board| (board.frame().0, board.particles()));
+
     let (onmousedown, ontouchstart) = if window.navigator().max_touch_points() > 0 {
         (Callback::from(|_| ()), ontouchstart)
     } else {
         (onmousedown, Callback::from(|_| ()))
     };
 
-    let width = WIDTH.to_string();
-    let height = HEIGHT.to_string();
+    let width = board_width.to_string();
+    let height = board_height.to_string();
+
+    // Pending garbage rises from the bottom of the left edge, armored rows in red on top.
+    let pending_rows = (game.pending.rows as f64).min(board_height as f64);
+    let pending_armored = (game.pending.armored as f64).min(board_height as f64 - pending_rows);
 
     html! {
         <svg style={format!("transform: scale({cell_size});")} width={width.clone()} height={height.clone()} onmousedown={onmousedown} ontouchstart={ontouchstart} ref={board_ref}>
-            <Board<WIDTH, HEIGHT>
+            <Board
+                width={board_width}
+                height={board_height}
                 floating_cells={floating_cells}
                 particles={particles} />
+            <rect x="0" y={(board_height as f64 - pending_rows).to_string()} width="0.1" height={pending_rows.to_string()} fill="#FFFFFF" />
+            <rect x="0" y={(board_height as f64 - pending_rows - pending_armored).to_string()} width="0.1" height={pending_armored.to_string()} fill="#FF2222" />
+            <text x="0" y="0" class="text" font-size="0.5px">
+                <tspan>{format!("SCORE: {}", game.score)}</tspan>
+                <tspan x="0" dy="1.2em">{format!("SENT: {}", game.sent)}</tspan>
+            </text>
+            if game.players > 2 {
+                <text x="0" y="1.2" class="text" font-size="0.5px" onmousedown={ontargetmousedown} ontouchstart={ontargettouchstart}>
+                    {format!("TARGET: {targeting}")}
+                </text>
+            }
+            if let Some((opponent_cells, opponent_particles)) = opponent_view {
+                <g transform={format!("translate({} 0.1) scale({OPPONENT_SCALE})", board_width as f64 * (1. - OPPONENT_SCALE) - 0.1)} opacity="0.7">
+                    <Board
+                        width={board_width}
+                        height={board_height}
+                        floating_cells={opponent_cells}
+                        particles={opponent_particles} />
+                </g>
+            }
+            if game.players > 2 {
+                <Opponents
+                    width={board_width}
+                    height={board_height}
+                    columns={board_width - 3}
+                    top={1.9}
+                    opponents={game.opponents.clone()}
+                    target={game.target} />
+            }
+            if let Some(result) = result.filter(|_| !game.board.is_animating()) {
+                <rect x="0" y="0" width={width} height={height} fill="rgba(0, 0, 0, 0.5)" />
+                <text x={center_x.clone()} y={(board_height as f64 / 3.).to_string()} class="text-center" font-size="1px">{result}</text>
+                if let Some(place) = game.place.filter(|_| game.players > 2) {
+                    <text x={center_x} y={(board_height as f64 / 2.).to_string()} class="text-center" font-size="0.5px">
+                        {format!("PLACE {place}/{}", game.players)}
+                    </text>
+                }
+                <Button x={board_width as f64 / 2.} y={board_height as f64 / 3. * 2.} font_size="0.5px" onclick={onrequeue}>{"Requeue"}</Button>
+            }
         </svg>
     }
 }
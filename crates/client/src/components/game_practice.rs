@@ -0,0 +1,95 @@
+use super::board::Board;
+use super::button::Button;
+use super::game::{raf_loop, use_board_input, use_board_sounds};
+use crate::game::{self, *};
+use yew::prelude::*;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub cell_size: f64,
+}
+
+#[function_component(GamePractice)]
+pub fn game_practice(props: &Props) -> Html {
+    let Props { cell_size } = props.clone();
+    let game = use_reducer(game::GamePractice::new);
+    let sounds = use_board_sounds();
+    let input = use_board_input(&game, cell_size, WIDTH, HEIGHT);
+
+    let cloned_game = game.clone();
+
+    use_effect_with_deps(
+        move |_| {
+            raf_loop(move || game.dispatch(PracticeGameAction::Animate));
+            || ()
+        },
+        (),
+    );
+
+    let game = cloned_game;
+
+    let cloned_game = game.clone();
+    let onundo = Callback::from(move |_| cloned_game.dispatch(PracticeGameAction::Undo));
+    let cloned_game = game.clone();
+    let onredo = Callback::from(move |_| cloned_game.dispatch(PracticeGameAction::Redo));
+    let cloned_game = game.clone();
+    let onretry = Callback::from(move |_| cloned_game.dispatch(PracticeGameAction::Retry));
+
+    let (floating_cells, frame_sounds) = game.board.frame();
+    sounds.play(&frame_sounds);
+
+    let particles = game.board.particles();
+
+    let width = WIDTH.to_string();
+    let height = HEIGHT.to_string();
+    let center_x = (WIDTH as f64 / 2.).to_string();
+    let upper_y = (HEIGHT as f64 / 3.).to_string();
+
+    let score_preview = input.preview_score(&game.board.board, game.scoring_rule());
+
+    let game_over = if game.is_over() && !game.board.is_animating() {
+        html! {
+            <>
+                <rect x="0" y="0" width={width.clone()} height={height.clone()} fill="rgba(0, 0, 0, 0.5)" />
+                <text x={center_x} y={upper_y} class="text-center" font-size="1px" dominant-baseline="hanging">{"GAME OVER"}</text>
+                <Button x={WIDTH as f64 / 2.} y={HEIGHT as f64 / 3. * 2.} font_size="0.5px" onclick={onretry}>{"Retry"}</Button>
+            </>
+        }
+    } else {
+        html! {}
+    };
+    let undo = if game.can_undo() {
+        html! {
+            <Button x={WIDTH as f64 - 2.5} y={0.4} font_size="0.4px" onclick={onundo}>{"Undo"}</Button>
+        }
+    } else {
+        html! {}
+    };
+    let redo = if game.can_redo() {
+        html! {
+            <Button x={WIDTH as f64 - 1.} y={0.4} font_size="0.4px" onclick={onredo}>{"Redo"}</Button>
+        }
+    } else {
+        html! {}
+    };
+
+    html! {
+        <svg style={format!("transform: scale({cell_size});")} width={width} height={height} onmousedown={input.onmousedown.clone()} ontouchstart={input.ontouchstart.clone()} onmousemove={input.onmousemove.clone()} ref={input.node_ref.clone()}>
+            {input.preview()}
+            <Board
+                width={WIDTH}
+                height={HEIGHT}
+                floating_cells={floating_cells}
+                particles={particles} />
+            {game_over}
+            <text x="0" y="0" class="text" font-size="0.5px">
+                <tspan>{format!("SCORE: {}", game.score)}</tspan>
+                if score_preview > 0 {
+                    <tspan dx="0.1em" opacity="0.6">{format!("+{score_preview}")}</tspan>
+                }
+            </text>
+            {undo}
+            {redo}
+        </svg>
+    }
+}
@@ -0,0 +1,202 @@
+use super::board::Board;
+use super::button::Button;
+use super::game::{raf_loop, use_board_input, use_board_sounds};
+use crate::game::{self, *};
+use crate::puzzle;
+use common::board::Grid;
+use common::puzzle::{Goal, Puzzle, PuzzlePack, Status};
+use std::rc::Rc;
+use yew::prelude::*;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub cell_size: f64,
+}
+
+/// The list of bundled puzzles, and the puzzle picked from it.
+#[function_component(GamePuzzle)]
+pub fn game_puzzle(props: &Props) -> Html {
+    let Props { cell_size } = props.clone();
+    let packs = use_state(|| None::<Rc<Vec<PuzzlePack>>>);
+    let selected = use_state(|| None::<Rc<Puzzle>>);
+    let completed = use_state(puzzle::completed);
+
+    let cloned_packs = packs.clone();
+    use_effect_with_deps(
+        move |_| {
+            wasm_bindgen_futures::spawn_local(async move {
+                cloned_packs.set(Some(Rc::new(puzzle::load_packs().await)));
+            });
+            || ()
+        },
+        (),
+    );
+
+    if let Some(selected_puzzle) = (*selected).clone() {
+        let cloned_selected = selected.clone();
+        let onback = Callback::from(move |_| cloned_selected.set(None));
+        let cloned_completed = completed.clone();
+        let onsolved = Callback::from(move |id: String| {
+            puzzle::mark_completed(&id);
+            cloned_completed.set(puzzle::completed());
+        });
+        let key = selected_puzzle.id.clone();
+        return html! {
+            <PuzzleBoard
+                key={key}
+                puzzle={selected_puzzle}
+                cell_size={cell_size}
+                onback={onback}
+                onsolved={onsolved} />
+        };
+    }
+
+    let select = |puzzle: &Puzzle| {
+        let cloned_selected = selected.clone();
+        let puzzle = Rc::new(puzzle.clone());
+        Callback::from(move |event: web_sys::MouseEvent| {
+            event.prevent_default();
+            cloned_selected.set(Some(puzzle.clone()));
+        })
+    };
+
+    let packs = match &*packs {
+        None => html! { <h2>{"Loading..."}</h2> },
+        Some(packs) => packs
+            .iter()
+            .map(|pack| {
+                let puzzles = pack.puzzles.iter().map(|puzzle| {
+                    let mark = if completed.contains(&puzzle.id) {
+                        " ✓"
+                    } else {
+                        ""
+                    };
+                    let stars = "★".repeat(puzzle.difficulty.unwrap_or(0));
+                    html! {
+                        <h3>
+                            <a href="#" onclick={select(puzzle)}>{&puzzle.title}</a>
+                            {format!(" {stars}{mark}")}
+                        </h3>
+                    }
+                });
+                html! {
+                    <>
+                        <h2>{&pack.name}</h2>
+                        {for puzzles}
+                    </>
+                }
+            })
+            .collect(),
+    };
+
+    html! {
+        <div class="app">
+            <h1>{"Puzzles"}</h1>
+            {packs}
+        </div>
+    }
+}
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct BoardProps {
+    pub puzzle: Rc<Puzzle>,
+    pub cell_size: f64,
+    pub onback: Callback<()>,
+    pub onsolved: Callback<String>,
+}
+
+#[function_component(PuzzleBoard)]
+pub fn puzzle_board(props: &BoardProps) -> Html {
+    let BoardProps {
+        puzzle,
+        cell_size,
+        onback,
+        onsolved,
+    } = props.clone();
+    let game = use_reducer(move || game::GamePuzzle::new(puzzle));
+    let sounds = use_board_sounds();
+    let (width, height) = (game.game.board.width(), game.game.board.height());
+    let input = use_board_input(&game, cell_size, width, height);
+
+    let cloned_game = game.clone();
+
+    use_effect_with_deps(
+        move |_| {
+            raf_loop(move || game.dispatch(PuzzleGameAction::Animate));
+            || ()
+        },
+        (),
+    );
+
+    let game = cloned_game;
+    let id = game.puzzle.id.clone();
+    use_effect_with_deps(
+        move |&status| {
+            if status == Status::Solved {
+                onsolved.emit(id);
+            }
+            || ()
+        },
+        game.status(),
+    );
+
+    let cloned_game = game.clone();
+    let onretry = Callback::from(move |_| cloned_game.dispatch(PuzzleGameAction::Retry));
+    let onback = Callback::from(move |_| onback.emit(()));
+
+    let (floating_cells, frame_sounds) = game.board.frame();
+    sounds.play(&frame_sounds);
+
+    let particles = game.board.particles();
+
+    let center_x = (width as f64 / 2.).to_string();
+    let upper_y = (height as f64 / 3.).to_string();
+
+    let progress = match game.game.goal {
+        Goal::ClearBombs { moves } => format!("MOVES: {}/{moves}", game.game.moves),
+        Goal::Score { score } => format!("SCORE: {}/{score}", game.game.score),
+        Goal::Survive { feeds } => format!("ROWS: {}/{feeds}", game.game.feeds),
+    };
+
+    let result = match game.status() {
+        _ if game.board.is_animating() => html! {},
+        Status::Playing => html! {},
+        status => {
+            let (message, retry) = if status == Status::Solved {
+                ("SOLVED", html! {})
+            } else {
+                (
+                    "FAILED",
+                    html! {
+                        <Button x={width as f64 / 2.} y={height as f64 / 3. * 2.} font_size="0.5px" onclick={onretry.clone()}>{"Retry"}</Button>
+                    },
+                )
+            };
+            html! {
+                <>
+                    <rect x="0" y="0" width={width.to_string()} height={height.to_string()} fill="rgba(0, 0, 0, 0.5)" />
+                    <text x={center_x} y={upper_y} class="text-center" font-size="1px" dominant-baseline="hanging">{message}</text>
+                    {retry}
+                    <Button x={width as f64 / 2.} y={height as f64 / 3. * 2. + 1.} font_size="0.5px" onclick={onback.clone()}>{"Back"}</Button>
+                </>
+            }
+        }
+    };
+
+    html! {
+        <svg style={format!("transform: scale({cell_size});")} width={width.to_string()} height={height.to_string()} onmousedown={input.onmousedown.clone()} ontouchstart={input.ontouchstart.clone()} onmousemove={input.onmousemove.clone()} ref={input.node_ref.clone()}>
+            {input.preview()}
+            <Board
+                width={width}
+                height={height}
+                floating_cells={floating_cells}
+                particles={particles} />
+            {result}
+            <text x="0" y="0" class="text" font-size="0.4px">
+                <tspan>{game.game.goal.to_string()}</tspan>
+                <tspan x="0" dy="1.2em">{progress}</tspan>
+            </text>
+            <Button x={width as f64 - 0.6} y={0.4} font_size="0.4px" onclick={onback}>{"Back"}</Button>
+        </svg>
+    }
+}
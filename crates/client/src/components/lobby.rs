@@ -0,0 +1,88 @@
+use crate::game;
+use yew::prelude::*;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub lobby: game::Lobby,
+    pub onqueue: Callback<()>,
+    pub oncreate: Callback<()>,
+    pub onjoin: Callback<String>,
+    pub onready: Callback<()>,
+    pub onleave: Callback<()>,
+}
+
+/// Queueing for a public match, or making and joining private rooms by code.
+#[function_component(Lobby)]
+pub fn lobby(props: &Props) -> Html {
+    let Props {
+        lobby,
+        onqueue,
+        oncreate,
+        onjoin,
+        onready,
+        onleave,
+    } = props.clone();
+    let code_ref = use_node_ref();
+
+    let link = |callback: Callback<()>| {
+        Callback::from(move |event: web_sys::MouseEvent| {
+            event.prevent_default();
+            callback.emit(());
+        })
+    };
+
+    let content = match lobby {
+        game::Lobby::Menu { unavailable } => {
+            let cloned_code_ref = code_ref.clone();
+            let onjoin = Callback::from(move |event: web_sys::MouseEvent| {
+                event.prevent_default();
+                if let Some(input) = cloned_code_ref.cast::<web_sys::HtmlInputElement>() {
+                    onjoin.emit(input.value());
+                }
+            });
+            let message = unavailable.map_or_else(
+                || html! {},
+                |code| html! { <p>{format!("Room {code} is full or does not exist")}</p> },
+            );
+            html! {
+                <>
+                    <h3><a href="#" onclick={link(onqueue)}>{"QUICK MATCH"}</a></h3>
+                    <h3><a href="#" onclick={link(oncreate)}>{"CREATE ROOM"}</a></h3>
+                    <h3>
+                        <input ref={code_ref} maxlength="4" placeholder="CODE" />
+                        {" "}
+                        <a href="#" onclick={onjoin}>{"JOIN ROOM"}</a>
+                    </h3>
+                    {message}
+                </>
+            }
+        }
+        game::Lobby::Queued => html! {
+            <>
+                <h2>{"Waiting for an opponent..."}</h2>
+                <h3><a href="#" onclick={link(onleave)}>{"CANCEL"}</a></h3>
+            </>
+        },
+        game::Lobby::Room {
+            code,
+            players,
+            ready,
+        } => html! {
+            <>
+                <h2>{format!("Room {code}")}</h2>
+                <p>{"Share the code with the players you want to play with."}</p>
+                <h3>{format!("{ready}/{players} ready")}</h3>
+                <h3><a href="#" onclick={link(onready)}>{"READY"}</a></h3>
+                <h3><a href="#" onclick={link(onleave)}>{"LEAVE"}</a></h3>
+            </>
+        },
+        game::Lobby::Playing => html! {},
+    };
+
+    html! {
+        <div class="app">
+            <h1>{"Online"}</h1>
+            {content}
+        </div>
+    }
+}
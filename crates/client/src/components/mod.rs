@@ -5,4 +5,9 @@ pub mod cell;
 pub mod game;
 pub mod game_hard;
 pub mod game_online;
+pub mod game_practice;
+pub mod game_puzzle;
+pub mod lobby;
+pub mod opponents;
 pub mod particle;
+pub mod spectate;
@@ -0,0 +1,74 @@
+use crate::game::OpponentBoard;
+use std::collections::BTreeMap;
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct Props {
+    /// The size of the player's board. Each opponent takes a cell of it across.
+    pub width: usize,
+    pub height: usize,
+    /// How many opponents fit in a row.
+    pub columns: usize,
+    /// Where the grid starts.
+    pub top: f64,
+    pub opponents: BTreeMap<usize, OpponentBoard>,
+    pub target: Option<usize>,
+}
+
+/// The opponents' boards shrunk to their column heights, the current target outlined in red.
+#[function_component(Opponents)]
+pub fn opponents(props: &Props) -> Html {
+    let &Props {
+        width,
+        height,
+        columns,
+        top,
+        ref opponents,
+        target,
+    } = props;
+    let scale = 0.9 / width as f64;
+    let board_height = height as f64 * scale;
+
+    let boards = opponents.iter().enumerate().map(|(i, (&id, opponent))| {
+        let x = (i % columns) as f64 + 0.05;
+        let y = top + (i / columns) as f64 * (board_height + 0.2);
+        let bars = opponent.heights.iter().enumerate().map(|(column, &stack)| {
+            html! {
+                <rect
+                    x={(column as f64 * scale).to_string()}
+                    y={((height - stack.min(height)) as f64 * scale).to_string()}
+                    width={scale.to_string()}
+                    height={(stack.min(height) as f64 * scale).to_string()}
+                    class="fill" />
+            }
+        });
+        let outline = if target == Some(id) {
+            html! { <rect width="0.9" height={board_height.to_string()} fill="none" stroke="#FF2222" stroke-width="0.04" /> }
+        } else {
+            html! { <rect width="0.9" height={board_height.to_string()} class="stroke" /> }
+        };
+        let place = opponent.place.map_or_else(
+            || html! {},
+            |place| html! {
+                <text x="0.45" y={(board_height / 2.).to_string()} class="text-center" font-size="0.4px">{place}</text>
+            },
+        );
+        let opacity = if opponent.place.is_some() { "0.3" } else { "0.6" };
+
+        html! {
+            <g key={id} transform={format!("translate({x} {y})")}>
+                <g opacity={opacity}>
+                    {outline}
+                    {for bars}
+                </g>
+                {place}
+            </g>
+        }
+    });
+
+    html! {
+        <>
+            {for boards}
+        </>
+    }
+}
@@ -35,12 +35,37 @@ pub fn particle(props: &Props) -> Html {
                 <circle cx={cx} cy={cy} r={r} opacity={opacity} stroke={color} stroke-width="0.01" fill="none" />
             }
         }
-        CellType::Tile => {
+        CellType::RowBomb | CellType::ColumnBomb | CellType::CrossBomb => {
+            let band = |horizontal: bool| {
+                let (x, y, width, height) = if horizontal {
+                    (cx - r, cy - 0.5, r * 2., 1.)
+                } else {
+                    (cx - 0.5, cy - r, 1., r * 2.)
+                };
+                html! {
+                    <rect x={x.to_string()} y={y.to_string()} width={width.to_string()} height={height.to_string()} stroke={color} stroke-width="0.01" fill="none" />
+                }
+            };
+            html! {
+                <g opacity={opacity}>
+                    if cell_type != CellType::ColumnBomb {
+                        {band(true)}
+                    }
+                    if cell_type != CellType::RowBomb {
+                        {band(false)}
+                    }
+                </g>
+            }
+        }
+        CellType::Tile | CellType::Armored => {
             let x = (cx - r).to_string();
             let y = (cy - r).to_string();
             let width = (r * 2.).to_string();
             let height = width.clone();
-            let style = format!("transform:rotate({}deg);", (expansion * 90.) as isize);
+            let style = format!(
+                "transform:rotate({}deg);stroke:{color};",
+                (expansion * 90.) as isize
+            );
             html! {
                 <rect x={x} y={y} width={width} height={height} opacity={opacity} style={style} class="rotate-center stroke" />
             }
@@ -0,0 +1,148 @@
+use super::board::Board;
+use super::button::Button;
+use super::game::raf_loop;
+use crate::game::{SpectateAction, HEIGHT, WIDTH};
+use crate::websocket::WebsocketBus;
+use common::board::Grid;
+use common::model::{RequestMessage, ResponseMessage};
+use yew::prelude::*;
+use yew_agent::use_bridge;
+
+/// Cells between two boards.
+const GAP: f64 = 1.;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub cell_size: f64,
+}
+
+/// Lists the matches being played and shows every board of the one chosen.
+#[function_component(Spectate)]
+pub fn spectate(props: &Props) -> Html {
+    let Props { cell_size } = props.clone();
+    let spectate = use_reducer(crate::game::Spectate::new);
+
+    let cloned_spectate = spectate.clone();
+    let websocket = use_bridge::<WebsocketBus, _>(move |msg: ResponseMessage| {
+        let action = match msg {
+            ResponseMessage::Games { games } => SpectateAction::Games(games),
+            ResponseMessage::Snapshot { boards } => SpectateAction::Snapshot(boards),
+            ResponseMessage::OpponentRemove { player, x, y } => {
+                SpectateAction::Remove(player, x, y)
+            }
+            ResponseMessage::OpponentFeed { player, row } => SpectateAction::Feed(player, row),
+            ResponseMessage::Placement { player, place } => {
+                SpectateAction::Placement(player, place)
+            }
+            ResponseMessage::GameOver => SpectateAction::GameOver,
+            _ => return,
+        };
+        cloned_spectate.dispatch(action);
+    });
+
+    let cloned_spectate = spectate.clone();
+    let cloned_ws = websocket.clone();
+    use_effect_with_deps(
+        move |_| {
+            cloned_ws.send(RequestMessage::ListGames);
+            raf_loop(move || cloned_spectate.dispatch(SpectateAction::Animate));
+            move || cloned_ws.send(RequestMessage::Leave)
+        },
+        (),
+    );
+
+    if spectate.watching.is_none() {
+        let games = spectate.games.iter().map(|game| {
+            let id = game.id;
+            let cloned_ws = websocket.clone();
+            let cloned_spectate = spectate.clone();
+            let onclick = Callback::from(move |event: web_sys::MouseEvent| {
+                event.prevent_default();
+                cloned_ws.send(RequestMessage::Spectate { game: id });
+                cloned_spectate.dispatch(SpectateAction::Watch(id));
+            });
+            html! {
+                <h3><a href="#" onclick={onclick}>{format!("MATCH {id} ({} PLAYERS)", game.players)}</a></h3>
+            }
+        });
+        let cloned_ws = websocket.clone();
+        let onrefresh = Callback::from(move |event: web_sys::MouseEvent| {
+            event.prevent_default();
+            cloned_ws.send(RequestMessage::ListGames);
+        });
+        return html! {
+            <div class="app">
+                <h1>{"Spectate"}</h1>
+                if spectate.games.is_empty() {
+                    <h2>{"No matches are being played"}</h2>
+                }
+                {for games}
+                <h3><a href="#" onclick={onrefresh}>{"REFRESH"}</a></h3>
+            </div>
+        };
+    }
+
+    // Every board of a match has the same size.
+    let (board_width, board_height) = spectate
+        .boards
+        .values()
+        .next()
+        .map_or((WIDTH, HEIGHT), |board| {
+            (board.board.width(), board.board.height())
+        });
+
+    // The boards are laid out in a grid as close to square as their number allows, shrunk to fit
+    // the space one board would take.
+    let count = spectate.boards.len().max(1);
+    let columns = (count as f64).sqrt().ceil() as usize;
+    let rows = count.div_ceil(columns);
+    let width = columns as f64 * (board_width as f64 + GAP) - GAP;
+    let height = rows as f64 * (board_height as f64 + GAP) - GAP;
+    let scale = cell_size * (board_width as f64 / width).min(board_height as f64 / height);
+
+    let boards = spectate.boards.iter().enumerate().map(|(i, (&player, board))| {
+        let x = (i % columns) as f64 * (board_width as f64 + GAP);
+        let y = (i / columns) as f64 * (board_height as f64 + GAP);
+        let (floating_cells, _) = board.frame();
+        let place = spectate.places.get(&player).map_or_else(
+            || html! {},
+            |place| html! {
+                <>
+                    <rect width={board_width.to_string()} height={board_height.to_string()} fill="rgba(0, 0, 0, 0.5)" />
+                    <text x={(board_width as f64 / 2.).to_string()} y={(board_height as f64 / 2.).to_string()} class="text-center" font-size="1px">
+                        {format!("PLACE {place}")}
+                    </text>
+                </>
+            },
+        );
+        html! {
+            <g key={player} transform={format!("translate({x} {y})")}>
+                <Board
+                    width={board_width}
+                    height={board_height}
+                    floating_cells={floating_cells}
+                    particles={board.particles()} />
+                {place}
+                <text x="0" y="0" class="text" font-size="0.5px">{format!("PLAYER {}", player + 1)}</text>
+            </g>
+        }
+    });
+
+    let cloned_ws = websocket.clone();
+    let cloned_spectate = spectate.clone();
+    let onback = Callback::from(move |event: web_sys::MouseEvent| {
+        event.prevent_default();
+        cloned_ws.send(RequestMessage::Leave);
+        cloned_ws.send(RequestMessage::ListGames);
+        cloned_spectate.dispatch(SpectateAction::Back);
+    });
+
+    html! {
+        <svg style={format!("transform: scale({scale});")} width={width.to_string()} height={height.to_string()}>
+            {for boards}
+            if spectate.is_over {
+                <Button x={width / 2.} y={height / 2.} font_size="0.5px" onclick={onback}>{"Back"}</Button>
+            }
+        </svg>
+    }
+}
@@ -24,6 +24,8 @@ pub struct CellAnimator {
     duration: usize,
     elapsed: usize,
     cell_type: CellType,
+    /// The column slid in from, and the frames the slide starts at and lasts.
+    slide: Option<(f64, usize, usize)>,
 }
 
 impl CellAnimator {
@@ -45,8 +47,15 @@ impl CellAnimator {
             duration,
             cell_type,
             elapsed: 0,
+            slide: None,
         }
     }
+
+    /// Slides the cell in sideways from column `from`, starting `delay` frames after the start.
+    pub fn slide_from(mut self, from: f64, delay: usize, duration: usize) -> Self {
+        self.slide = Some((from, delay, duration));
+        self
+    }
 }
 
 impl Animation for CellAnimator {
@@ -59,9 +68,17 @@ impl Animation for CellAnimator {
     fn current_frame(&self) -> FloatingCell {
         let relative_time = self.elapsed.saturating_sub(self.delay).min(self.duration) as f64
             / self.duration as f64;
+        let x = match self.slide {
+            Some((from, delay, duration)) => {
+                let position =
+                    self.elapsed.saturating_sub(delay).min(duration) as f64 / duration as f64;
+                interpolation((from, self.x), position)
+            }
+            None => self.x,
+        };
         FloatingCell {
             id: self.id,
-            x: self.x,
+            x,
             y: interpolation(self.y, relative_time),
             cell_type: self.cell_type,
             opacity: interpolation(self.opacity, relative_time),
@@ -69,7 +86,10 @@ impl Animation for CellAnimator {
     }
 
     fn is_over(&self) -> bool {
-        self.duration + self.delay <= self.elapsed
+        let slide_end = self
+            .slide
+            .map_or(0, |(_, delay, duration)| delay + duration);
+        self.duration + self.delay <= self.elapsed && slide_end <= self.elapsed
     }
 }
 
@@ -184,6 +204,33 @@ impl Animation for NumberAnimator {
     }
 }
 
+/// Counts frames since the game began. Grade decay and replays use it as their clock.
+pub struct FrameCounter {
+    frames: usize,
+}
+
+impl FrameCounter {
+    pub fn new() -> Self {
+        FrameCounter { frames: 0 }
+    }
+}
+
+impl Animation for FrameCounter {
+    type Frame = usize;
+
+    fn advance_frames(&mut self, frames: usize) {
+        self.frames += frames;
+    }
+
+    fn current_frame(&self) -> usize {
+        self.frames
+    }
+
+    fn is_over(&self) -> bool {
+        false
+    }
+}
+
 pub enum Sound {
     Break,
     Fall,
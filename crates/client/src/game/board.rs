@@ -1,14 +1,33 @@
-use super::{animation::*, HEIGHT, WIDTH};
+use super::animation::*;
 use crate::animation::*;
-use common::board::{Board, Cell, CellType};
-use std::cell::RefCell;
-use std::collections::BTreeSet;
+use common::board::{Cell, CellType, DynamicBoard, FallDistance, Grid, Move};
+use common::chain::{ChainReport, RemovedCell};
+use common::history::Operation;
+use std::cell::{OnceCell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
 use std::rc::Rc;
 
 const PARTICLE_COLORS: [&str; 7] = [
     "#FF0000", "#FF8800", "#FFFF00", "#00FF00", "#00FFFF", "#0000FF", "#FF00FF",
 ];
 
+fn particle_style(
+    cell_type: CellType,
+    wave: usize,
+    width: usize,
+    height: usize,
+) -> (&'static str, (f64, f64), usize) {
+    let color = PARTICLE_COLORS[wave % PARTICLE_COLORS.len()];
+    match cell_type {
+        CellType::Tile => ("#FFFFFF", (0., 1.), 10),
+        CellType::Armored => ("#888888", (0., 1.), 10),
+        CellType::Bomb => (color, (0., 3.), 40),
+        CellType::CrossBomb => (color, (0., 5.), 40),
+        CellType::RowBomb => (color, (0., width as f64 * 2.), 40),
+        CellType::ColumnBomb => (color, (0., height as f64 * 2.), 40),
+    }
+}
+
 #[derive(Clone, PartialEq)]
 pub enum VisibleState {
     Visible,
@@ -46,7 +65,9 @@ impl<T> Animation for SingleAnimation<T> {
 
 #[derive(Clone)]
 pub struct AnimatedBoard {
-    pub board: Board<WIDTH, HEIGHT>,
+    pub board: DynamicBoard,
+    /// The moves on the board, found the first time they are asked for after it changes.
+    moves: Rc<OnceCell<Vec<Move>>>,
     pub visible: VisibleState,
     #[allow(clippy::type_complexity)]
     pub animator: Rc<
@@ -60,9 +81,10 @@ pub struct AnimatedBoard {
 }
 
 impl AnimatedBoard {
-    pub fn new() -> Self {
+    pub fn new(width: usize, height: usize) -> Self {
         AnimatedBoard {
-            board: Board::new(),
+            board: DynamicBoard::new(width, height),
+            moves: Rc::default(),
             visible: Visible,
             animator: Rc::new(RefCell::new(FloatAnimator::new(Box::new(
                 AnimationStream::new(),
@@ -73,22 +95,34 @@ impl AnimatedBoard {
         }
     }
 
-    pub fn feed(&mut self, row: &[CellType; WIDTH]) {
+    pub fn feed(&mut self, row: &[CellType]) {
         self.board.feed(row);
+        self.feed_animation();
+    }
+
+    /// Replaces the board with `board`, bringing its cells in like a fed row.
+    pub fn load(&mut self, board: DynamicBoard) {
+        self.board = board;
+        self.feed_animation();
+    }
+
+    fn feed_animation(&mut self) {
+        self.moves = Rc::default();
         let visible = self.visible == Visible;
+        let height = self.board.height();
 
         let feed_animation = self
             .board
-            .cells
+            .cells()
             .iter()
             .enumerate()
             .flat_map(|(x, col)| {
                 col.iter().enumerate().flat_map(move |(y, cell)| {
                     cell.map(|cell| {
                         let Cell { id, cell_type } = cell;
-                        let opacity = if visible || y == HEIGHT - 1 {
+                        let opacity = if visible || y == height - 1 {
                             (1., 1.)
-                        } else if y == HEIGHT - 2 {
+                        } else if y == height - 2 {
                             (1., 0.)
                         } else {
                             (0., 0.)
@@ -119,22 +153,34 @@ impl AnimatedBoard {
         );
     }
 
-    pub fn remove(&mut self, x: usize, y: usize) -> (usize, usize) {
-        let dists = self.board.remove(x, y);
-
-        if dists.is_empty() {
-            return (0, 0);
+    pub fn remove(&mut self, x: usize, y: usize) -> ChainReport {
+        let report = self.board.remove(x, y);
+        if !report.is_empty() {
+            self.remove_animation(&report);
         }
+        report
+    }
+
+    fn remove_animation(&mut self, report: &ChainReport) {
+        self.moves = Rc::default();
 
         let visible = self.visible == Visible;
+        let (width, height) = (self.board.width(), self.board.height());
 
-        let particles = dists
+        let particles = report
+            .removed
             .iter()
-            .map(|&(id, dist, x, y, cell_type)| {
-                let (color, expansion, duration) = match cell_type {
-                    CellType::Bomb => (PARTICLE_COLORS[dist % 7], (0., 3.), 40),
-                    CellType::Tile => ("#FFFFFF", (0., 1.), 10),
-                };
+            .chain(report.damaged.iter())
+            .map(|cell| {
+                let &RemovedCell {
+                    id,
+                    distance,
+                    x,
+                    y,
+                    cell_type,
+                } = cell;
+                let (color, expansion, duration) =
+                    particle_style(cell_type, distance, width, height);
                 ParticleAnimator::new(
                     id + 1_000_000,
                     color,
@@ -143,7 +189,7 @@ impl AnimatedBoard {
                     y as f64,
                     expansion,
                     (1., 0.),
-                    dist * 3,
+                    distance * 3,
                     duration,
                 )
             })
@@ -151,14 +197,14 @@ impl AnimatedBoard {
 
         let remove_animation = self
             .board
-            .cells
+            .cells()
             .iter()
             .enumerate()
             .flat_map(|(x, col)| {
                 col.iter().enumerate().flat_map(move |(y, cell)| {
                     cell.map(|cell| {
                         let Cell { cell_type, id } = cell;
-                        let opacity = if !visible && y != HEIGHT - 1 { 0. } else { 1. };
+                        let opacity = if !visible && y != height - 1 { 0. } else { 1. };
                         Box::new(CellAnimator::new(
                             id,
                             x as f64,
@@ -171,61 +217,61 @@ impl AnimatedBoard {
                     })
                 })
             })
-            .chain(dists.iter().map(|&(id, dist, x, y, cell_type)| {
+            .chain(report.removed.iter().map(|cell| {
                 Box::new(CellAnimator::new(
-                    id,
-                    x as f64,
-                    (y as f64, y as f64),
+                    cell.id,
+                    cell.x as f64,
+                    (cell.y as f64, cell.y as f64),
                     (1., 0.),
-                    dist * 3,
+                    cell.distance * 3,
                     10,
-                    cell_type,
+                    cell.cell_type,
                 )) as Box<dyn Animation<Frame = FloatingCell>>
             }))
             .collect();
-        let mut remove_sounds = dists
-            .iter()
-            .flat_map(|&(_, dist, _, _, cell_type)| {
-                if cell_type == CellType::Bomb || dist == 0 {
-                    Some((dist * 3, Sound::Break))
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
-        remove_sounds.sort_by_key(|x| x.0);
-        remove_sounds.dedup_by_key(|x| x.0);
+        let remove_sounds = report
+            .bombs_per_wave()
+            .into_iter()
+            .enumerate()
+            .filter(|&(dist, bombs)| dist == 0 || bombs > 0)
+            .map(|(dist, _)| (dist * 3, Sound::Break))
+            .collect();
         self.animator.borrow_mut().animation.push(
             Animator::new(remove_animation)
                 .zip(SoundPlayer::new(remove_sounds))
                 .zip(SingleAnimation::new(particles)),
         );
-        let bombs = dists.iter().filter(|x| x.4 == CellType::Bomb).count();
-        (dists.len(), bombs)
     }
 
     pub fn apply_gravity(&mut self) {
         let dists = self.board.apply_gravity();
+        self.fall_animation(&dists);
+    }
+
+    /// Cells fall first, and once the longest fall is over, slide into the empty columns.
+    fn fall_animation(&mut self, dists: &FallDistance) {
+        self.moves = Rc::default();
         let visible = self.visible == Visible;
+        let height = self.board.height();
+        let slide_delay = dists.down.values().max().map_or(0, |dist| dist * 5 + 1);
         let fall_animation = self
             .board
-            .cells
+            .cells()
             .iter()
             .enumerate()
             .flat_map(|(x, col)| {
-                let dists = dists.clone();
                 col.iter().enumerate().flat_map(move |(y, cell)| {
                     cell.map(|cell| {
                         let Cell { id, cell_type } = cell;
-                        let dist = dists.get(&id).cloned().unwrap_or(0);
-                        let opacity = if visible || y - dist == HEIGHT - 1 {
+                        let dist = dists.down.get(&id).cloned().unwrap_or(0);
+                        let opacity = if visible || y - dist == height - 1 {
                             (1., 1.)
-                        } else if y == HEIGHT - 1 {
+                        } else if y == height - 1 {
                             (0., 1.)
                         } else {
                             (0., 0.)
                         };
-                        Box::new(CellAnimator::new(
+                        let mut animator = CellAnimator::new(
                             id,
                             x as f64,
                             ((y - dist) as f64, y as f64),
@@ -233,18 +279,25 @@ impl AnimatedBoard {
                             0,
                             dist * 5 + 1,
                             cell_type,
-                        )) as Box<dyn Animation<Frame = FloatingCell>>
+                        );
+                        if let Some(&left) = dists.left.get(&id) {
+                            animator =
+                                animator.slide_from((x + left) as f64, slide_delay, left * 5 + 1);
+                        }
+                        Box::new(animator) as Box<dyn Animation<Frame = FloatingCell>>
                     })
                 })
             })
             .collect();
-        let mut dist_set = BTreeSet::new();
-        for (_, &dist) in dists.iter() {
-            dist_set.insert(dist);
-        }
-        let fall_sounds = dist_set
-            .iter()
-            .map(|dist| (dist * 5 + 1, Sound::Fall))
+        let fall_ends: BTreeSet<_> = dists
+            .down
+            .values()
+            .map(|dist| dist * 5 + 1)
+            .chain(dists.left.values().map(|left| slide_delay + left * 5 + 1))
+            .collect();
+        let fall_sounds = fall_ends
+            .into_iter()
+            .map(|end| (end, Sound::Fall))
             .collect();
         self.animator.borrow_mut().animation.push(
             Animator::new(fall_animation)
@@ -253,20 +306,145 @@ impl AnimatedBoard {
         );
     }
 
+    /// Animates an operation that was already applied to `self.board`.
+    pub fn show(&mut self, operation: &Operation) {
+        match operation {
+            Operation::Remove(report) => self.remove_animation(report),
+            Operation::Gravity(falls) => {
+                let mut dists = FallDistance::default();
+                for fall in falls {
+                    if fall.to.1 > fall.from.1 {
+                        dists.down.insert(fall.id, fall.to.1 - fall.from.1);
+                    }
+                    if fall.from.0 > fall.to.0 {
+                        dists.left.insert(fall.id, fall.from.0 - fall.to.0);
+                    }
+                }
+                self.fall_animation(&dists);
+            }
+            Operation::Feed { .. } => self.feed_animation(),
+        }
+    }
+
+    pub fn play(&mut self, operation: &Operation) {
+        operation.apply(&mut self.board);
+        self.show(operation);
+    }
+
+    /// Takes an operation back, animating the cells along the reverse of their motion.
+    pub fn rewind(&mut self, operation: &Operation) {
+        operation.revert(&mut self.board);
+        self.moves = Rc::default();
+        let height = self.board.height();
+
+        let slides: BTreeMap<_, _> = match operation {
+            Operation::Gravity(falls) => falls
+                .iter()
+                .filter(|fall| fall.from.0 > fall.to.0)
+                .map(|fall| {
+                    let duration = (fall.from.0 - fall.to.0) * 5 + 1;
+                    (fall.id, (fall.to.0 as f64, duration))
+                })
+                .collect(),
+            _ => BTreeMap::new(),
+        };
+        let (motions, sunk_row): (BTreeMap<_, _>, &[Cell]) = match operation {
+            Operation::Remove(report) => {
+                let depth = report.depth();
+                let restored = report
+                    .removed
+                    .iter()
+                    .map(|cell| {
+                        let delay = (depth - cell.distance) * 3;
+                        (cell.id, (cell.y as f64, (0., 1.), delay, 10))
+                    })
+                    .collect();
+                (restored, &[])
+            }
+            Operation::Gravity(falls) => {
+                // Cells slide back out of collapsed columns first, then rise.
+                let rises = falls
+                    .iter()
+                    .map(|fall| {
+                        let slide = slides.get(&fall.id).map_or(0, |&(_, duration)| duration);
+                        let duration = (fall.to.1 - fall.from.1) * 5 + 1;
+                        (fall.id, (fall.to.1 as f64, (1., 1.), slide, duration))
+                    })
+                    .collect();
+                (rises, &[])
+            }
+            Operation::Feed { row, .. } => (BTreeMap::new(), row),
+        };
+        let shift = if sunk_row.is_empty() { 0. } else { 1. };
+
+        let rewind_animation = self
+            .board
+            .cells()
+            .iter()
+            .enumerate()
+            .flat_map(|(x, col)| {
+                let (motions, slides) = (&motions, &slides);
+                col.iter().enumerate().flat_map(move |(y, cell)| {
+                    cell.map(|cell| {
+                        let Cell { id, cell_type } = cell;
+                        let (from, opacity, delay, duration) = motions
+                            .get(&id)
+                            .cloned()
+                            .unwrap_or((y as f64 - shift, (1., 1.), 0, 10));
+                        let mut animator = CellAnimator::new(
+                            id,
+                            x as f64,
+                            (from, y as f64),
+                            opacity,
+                            delay,
+                            duration,
+                            cell_type,
+                        );
+                        if let Some(&(from_x, slide)) = slides.get(&id) {
+                            animator = animator.slide_from(from_x, 0, slide);
+                        }
+                        Box::new(animator) as Box<dyn Animation<Frame = FloatingCell>>
+                    })
+                })
+            })
+            .chain(
+                sunk_row
+                    .iter()
+                    .enumerate()
+                    .map(|(x, &Cell { id, cell_type })| {
+                        Box::new(CellAnimator::new(
+                            id,
+                            x as f64,
+                            ((height - 1) as f64, height as f64),
+                            (1., 0.),
+                            0,
+                            10,
+                            cell_type,
+                        )) as Box<dyn Animation<Frame = FloatingCell>>
+                    }),
+            )
+            .collect();
+
+        self.animator.borrow_mut().animation.push(
+            Animator::new(rewind_animation)
+                .zip(SoundPlayer::new(Vec::new()))
+                .zip(SingleAnimation::new(Vec::new())),
+        );
+    }
+
     pub fn reset(&mut self) {
+        let (width, height) = (self.board.width(), self.board.height());
         let particles = self
             .board
-            .cells
+            .cells()
             .iter()
             .enumerate()
             .flat_map(|(x, col)| {
                 col.iter().enumerate().flat_map(move |(y, cell)| {
                     cell.map(|cell| {
                         let Cell { cell_type, id } = cell;
-                        let (color, expansion, duration) = match cell_type {
-                            CellType::Bomb => (PARTICLE_COLORS[HEIGHT - y - 1], (0., 3.), 40),
-                            CellType::Tile => ("#FFFFFF", (0., 1.), 10),
-                        };
+                        let (color, expansion, duration) =
+                            particle_style(cell_type, height - y - 1, width, height);
                         ParticleAnimator::new(
                             id + 1_000_000,
                             color,
@@ -275,7 +453,7 @@ impl AnimatedBoard {
                             y as f64,
                             expansion,
                             (1., 0.),
-                            (HEIGHT - y - 1) * 10,
+                            (height - y - 1) * 10,
                             duration,
                         )
                     })
@@ -285,7 +463,7 @@ impl AnimatedBoard {
 
         let reset_animation = self
             .board
-            .cells
+            .cells()
             .iter()
             .enumerate()
             .flat_map(|(x, col)| {
@@ -297,7 +475,7 @@ impl AnimatedBoard {
                             x as f64,
                             (y as f64, y as f64),
                             (1., 0.),
-                            (HEIGHT - y - 1) * 10,
+                            (height - y - 1) * 10,
                             10,
                             cell_type,
                         )) as Box<dyn Animation<Frame = FloatingCell>>
@@ -311,14 +489,20 @@ impl AnimatedBoard {
                 .zip(SoundPlayer::new(Vec::new()))
                 .zip(SingleAnimation::new(particles)),
         );
-        self.board.cells = [[None; HEIGHT]; WIDTH];
+        self.board.clear();
+        self.moves = Rc::default();
     }
 
-    pub fn is_filled(&self) -> bool {
-        self.board
-            .cells
+    /// The move that detonating `(x, y)` makes, if the cell is filled.
+    pub fn move_at(&self, x: usize, y: usize) -> Option<&Move> {
+        self.moves
+            .get_or_init(|| self.board.moves())
             .iter()
-            .any(|x| x.first().cloned().flatten().is_some())
+            .find(|m| m.triggers.contains(&(x, y)))
+    }
+
+    pub fn is_filled(&self) -> bool {
+        self.board.is_filled()
     }
 
     pub fn animate(&self) {
@@ -328,16 +512,17 @@ impl AnimatedBoard {
 
     pub fn frame(&self) -> (Vec<FloatingCell>, Vec<Sound>) {
         let (frame, particles) = self.animator.borrow().frame().unwrap_or_else(|| {
+            let height = self.board.height();
             let cells = self
                 .board
-                .cells
+                .cells()
                 .iter()
                 .enumerate()
                 .flat_map(|(x, column)| {
                     column.iter().enumerate().flat_map(move |(y, cell)| {
                         cell.map(|cell| {
                             let Cell { id, cell_type } = cell;
-                            let opacity = if self.visible == Invisible && y != HEIGHT - 1 {
+                            let opacity = if self.visible == Invisible && y != height - 1 {
                                 0.
                             } else {
                                 1.
@@ -1,218 +1,16 @@
 use super::{
     animation::*,
     board::{AnimatedBoard, VisibleState::*},
-    GameAction, WIDTH,
+    random_seed, GameAction, HEIGHT, WIDTH,
 };
 use crate::animation::{Animation, FloatAnimator};
-use common::board::CellType;
-use rand::prelude::*;
+use common::engine::{Event, GameState};
+use common::history::Operation;
+use common::replay::{Action, Mode, Replay, Rules};
 use std::cell::RefCell;
 use std::rc::Rc;
 use yew::Reducible;
 
-fn cumulate(iter: impl Iterator<Item = usize>) -> impl Iterator<Item = usize> {
-    iter.scan(0, |acc, x| {
-        *acc += x;
-        Some(*acc)
-    })
-}
-
-#[derive(Clone)]
-struct SpreadBombGenerator {
-    rng: StdRng,
-    generated: [usize; WIDTH],
-}
-
-impl SpreadBombGenerator {
-    fn new() -> Self {
-        let random = js_sys::Math::random();
-        let rng = StdRng::seed_from_u64(u64::from_be_bytes(random.to_be_bytes()));
-        SpreadBombGenerator {
-            rng,
-            generated: [0; WIDTH],
-        }
-    }
-
-    fn possibility(&self) -> Vec<usize> {
-        let max = self.generated.iter().copied().max().unwrap();
-        self.generated
-            .iter()
-            .map(|x| 1 << ((max - x) * 2))
-            .collect()
-    }
-
-    fn next_double(&mut self) -> (usize, usize) {
-        let mut possibility = self.possibility();
-
-        let mut sum = possibility.iter().sum::<usize>();
-        let r = self.rng.gen_range(0..sum);
-
-        let left = cumulate(possibility.iter().copied())
-            .enumerate()
-            .filter(|&(_, x)| r < x)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap();
-
-        sum -= possibility[left];
-        possibility[left] = 0;
-        let r = self.rng.gen_range(0..sum);
-
-        let right = cumulate(possibility.iter().copied())
-            .enumerate()
-            .filter(|&(_, x)| r < x)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap();
-
-        self.generated[left] += 1;
-        self.generated[right] += 1;
-        (left, right)
-    }
-
-    fn next_single(&mut self) -> usize {
-        let possibility = self.possibility();
-
-        let sum = possibility.iter().sum::<usize>();
-        let r = self.rng.gen_range(0..sum);
-
-        let bomb = cumulate(possibility.iter().copied())
-            .enumerate()
-            .filter(|&(_, x)| r < x)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap();
-
-        self.generated[bomb] += 1;
-        bomb
-    }
-
-    fn reset(&mut self) {
-        self.generated = [0; WIDTH];
-    }
-}
-
-pub struct Grade {
-    grade: &'static str,
-    decay_rate: usize,
-    required_score: isize,
-    score_offset: usize,
-}
-
-impl Grade {
-    const fn new(
-        grade: &'static str,
-        decay_rate: usize,
-        required_score: isize,
-        score_offset: usize,
-    ) -> Grade {
-        Grade {
-            grade,
-            decay_rate,
-            required_score,
-            score_offset,
-        }
-    }
-}
-
-static RANKS: [Grade; 19] = [
-    Grade::new("C3", 120, 75, 0),
-    Grade::new("C2", 90, 75, 0),
-    Grade::new("C1", 90, 100, 0),
-    Grade::new("B3", 75, 85, 0),
-    Grade::new("B2", 75, 85, 0),
-    Grade::new("B1", 75, 130, 0),
-    Grade::new("A3", 60, 100, 1),
-    Grade::new("A2", 60, 100, 1),
-    Grade::new("A1", 60, 200, 1),
-    Grade::new("S1", 50, 200, 2),
-    Grade::new("S2", 45, 200, 2),
-    Grade::new("S3", 40, 200, 2),
-    Grade::new("S4", 35, 200, 3),
-    Grade::new("S5", 30, 200, 3),
-    Grade::new("S6", 25, 200, 3),
-    Grade::new("S7", 20, 200, 4),
-    Grade::new("S8", 15, 200, 4),
-    Grade::new("S9", 10, 250, 5),
-    Grade::new("master", 10, 1000000, 1000),
-];
-
-pub struct GradeManager {
-    score: isize,
-    current_grade: usize,
-    max_chain_per_section: [usize; 10],
-    elapsed_frames: f64,
-    last_timestamp: f64,
-}
-
-impl GradeManager {
-    fn new() -> GradeManager {
-        GradeManager {
-            score: 0,
-            current_grade: 0,
-            max_chain_per_section: [0; 10],
-            elapsed_frames: 0.,
-            last_timestamp: js_sys::Date::now(),
-        }
-    }
-
-    fn decay(&mut self) {
-        let now = js_sys::Date::now();
-        self.elapsed_frames += (now - self.last_timestamp) / 1000. * 60.;
-        self.last_timestamp = now;
-
-        let decay_rate = self.current_grade().decay_rate;
-        let decay = self.elapsed_frames as usize / decay_rate;
-        self.score -= decay as isize;
-        self.elapsed_frames -= (decay * decay_rate) as f64;
-
-        if self.current_grade == 0 && self.score < 0 {
-            self.score = 0;
-        }
-    }
-
-    fn add(&mut self, section: usize, bombs: usize) -> bool {
-        fn sqrt(n: usize) -> usize {
-            (0..).take_while(|x| x * x <= n).last().unwrap()
-        }
-
-        if self.current_grade().grade == "S9" && section == 9 && self.max_chain_per_section[9] > 0 {
-            return false;
-        }
-
-        let score_offset = self.current_grade().score_offset;
-        let score = (sqrt(bombs * bombs * bombs) * (section / 2 + 1)).saturating_sub(score_offset);
-
-        self.score += score as isize;
-        self.max_chain_per_section[section] = self.max_chain_per_section[section].max(bombs);
-
-        let is_promoted = self.score >= self.current_grade().required_score;
-        while self.score >= self.current_grade().required_score {
-            if (section < 9 || !self.fulfills_master_condition())
-                && self.current_grade().grade == "S9"
-            {
-                return false;
-            }
-            self.score -= self.current_grade().required_score;
-            self.current_grade += 1;
-        }
-
-        is_promoted
-    }
-
-    fn current_grade(&self) -> &'static Grade {
-        &RANKS[self.current_grade]
-    }
-
-    fn fulfills_master_condition(&self) -> bool {
-        let section_conditions = self.max_chain_per_section[0..=8].iter().all(|&x| x >= 5);
-        let current_grade = self.current_grade();
-        current_grade.grade == "S9"
-            && self.score >= current_grade.required_score
-            && section_conditions
-    }
-}
-
 struct GradeAnimation {
     frame_since_promotion: usize,
 }
@@ -286,13 +84,8 @@ impl Animation for Timer {
 #[derive(Clone)]
 pub struct GameHard {
     pub board: AnimatedBoard,
-    generator: SpreadBombGenerator,
-    grade: Rc<RefCell<GradeManager>>,
-    pub until_single: usize,
-    pub single_frequency: usize,
-    pub section: usize,
-    pub level: usize,
-    pub level_limit: usize,
+    state: Rc<RefCell<GameState>>,
+    clock: Rc<RefCell<FloatAnimator<FrameCounter>>>,
     pub timer: Rc<RefCell<FloatAnimator<Timer>>>,
     pub is_started: bool,
     sounds: Rc<RefCell<Vec<Sound>>>,
@@ -301,15 +94,20 @@ pub struct GameHard {
 
 impl GameHard {
     pub fn new() -> Self {
-        GameHard {
-            board: AnimatedBoard::new(),
-            generator: SpreadBombGenerator::new(),
-            grade: Rc::new(RefCell::new(GradeManager::new())),
-            until_single: 999,
-            single_frequency: 999,
-            section: 0,
-            level: 0,
+        let rules = Rules {
+            width: WIDTH,
+            height: HEIGHT,
             level_limit: 999,
+            ..Rules::default()
+        };
+        let state = GameState::new(Mode::Master, random_seed(), rules);
+        // Same board settings as the engine's.
+        let mut board = AnimatedBoard::new(WIDTH, HEIGHT);
+        board.board = state.board().clone();
+        GameHard {
+            board,
+            state: Rc::new(RefCell::new(state)),
+            clock: Rc::new(RefCell::new(FloatAnimator::new(Box::new(FrameCounter::new())))),
             timer: Rc::new(RefCell::new(FloatAnimator::new(Box::new(Timer::new(60))))),
             is_started: false,
             sounds: Rc::new(RefCell::new(Vec::new())),
@@ -320,39 +118,35 @@ impl GameHard {
     }
 
     pub fn is_over(&self) -> bool {
-        let reached_limit = self.level >= self.level_limit;
-        self.board.is_filled() || reached_limit
+        self.state.borrow().is_over()
     }
 
-    pub fn next_row(&mut self) -> [CellType; WIDTH] {
-        if self.until_single == 0 {
-            self.until_single = self.single_frequency - 1;
-            let bomb = self.generator.next_single();
-            let mut row = [CellType::Tile; WIDTH];
-            row[bomb] = CellType::Bomb;
-            row
-        } else {
-            let bombs = self.generator.next_double();
-            let mut row = [CellType::Tile; WIDTH];
-            row[bombs.0] = CellType::Bomb;
-            row[bombs.1] = CellType::Bomb;
-            self.until_single -= 1;
-            row
-        }
+    pub fn level(&self) -> usize {
+        self.state.borrow().level()
+    }
+
+    pub fn level_limit(&self) -> usize {
+        self.state.borrow().limit()
+    }
+
+    pub fn until_single(&self) -> usize {
+        self.state.borrow().until_single()
+    }
+
+    pub fn single_frequency(&self) -> usize {
+        self.state.borrow().single_frequency()
     }
 
     pub fn grade(&self) -> &'static str {
-        let grade = self.grade.borrow().current_grade().grade;
-        if grade == "master" && self.level >= self.level_limit && !self.board.is_filled() {
-            "Grandmaster"
-        } else {
-            grade
-        }
+        self.state.borrow().grade()
     }
 
     pub fn grade_condition(&self) -> (isize, isize) {
-        let grade = self.grade.borrow();
-        (grade.score, grade.current_grade().required_score)
+        self.state.borrow().grade_condition()
+    }
+
+    pub fn replay(&self) -> Replay {
+        self.state.borrow().replay().clone()
     }
 
     pub fn sounds(&self) -> Vec<Sound> {
@@ -362,9 +156,38 @@ impl GameHard {
     pub fn grade_zoom_rate(&self) -> f64 {
         self.grade_animation.borrow().frame()
     }
-}
 
-pub const SINGLE_FREQUENCY: [usize; 10] = [9999, 9, 8, 7, 6, 5, 4, 3, 2, 2];
+    fn apply(&mut self, action: Action) {
+        let frame = self.clock.borrow().frame();
+        let events = self.state.borrow_mut().apply(frame, action);
+        for event in events {
+            match event {
+                Event::Removed { report, .. } => {
+                    self.board.play(&Operation::Remove(report));
+                    let gravity = Operation::gravity(&mut self.board.board);
+                    self.board.show(&gravity);
+                }
+                Event::Fed(row) => self.board.feed(&row),
+                Event::SectionChanged {
+                    invisible: true, ..
+                } => {
+                    self.board.reset();
+                    self.board.visible = Invisible;
+                }
+                Event::SectionChanged { .. } => (),
+                Event::Promoted(_) => {
+                    self.sounds.borrow_mut().push(Sound::LevelUp);
+                    self.grade_animation.borrow_mut().animation.promote();
+                }
+                Event::GameOver => {
+                    if self.board.visible == Invisible {
+                        self.board.visible = InvisibleWhileAnimation;
+                    }
+                }
+            }
+        }
+    }
+}
 
 impl Reducible for GameHard {
     type Action = GameAction;
@@ -373,59 +196,20 @@ impl Reducible for GameHard {
         let mut game = (*self).clone();
 
         match action {
-            GameAction::Remove(x, y) => {
-                if game.is_over() {
-                    return game.into();
-                }
-
-                let (removed_cells, removed_bombs) = game.board.remove(x, y);
-                if removed_cells > 0 {
-                    game.level += removed_bombs;
-                    let section = (game.level / 100).min(9);
-
-                    let is_promoted = game.grade.borrow_mut().add(section, removed_bombs);
-
-                    if game.section < section {
-                        game.section = section;
-                        game.single_frequency = SINGLE_FREQUENCY[section];
-                        game.until_single = game.single_frequency;
-                        if game.section == 9 && game.grade() == "master" {
-                            game.level = 900;
-                            game.board.reset();
-                            game.generator.reset();
-                            game.board.visible = Invisible;
-                        }
-                    }
-
-                    game.board.apply_gravity();
-                    let row = game.next_row();
-                    game.board.feed(&row);
-
-                    if is_promoted || game.grade() == "Grandmaster" {
-                        self.sounds.borrow_mut().push(Sound::LevelUp);
-                        self.grade_animation.borrow_mut().animation.promote();
-                    }
-
-                    if game.is_over() && game.board.visible == Invisible {
-                        game.board.visible = InvisibleWhileAnimation;
-                    }
-                }
-            }
-            GameAction::Feed => {
-                let row = game.next_row();
-                game.board.feed(&row);
-            }
+            GameAction::Remove(x, y) => game.apply(Action::Remove { x, y }),
+            GameAction::Feed => game.apply(Action::Feed),
             GameAction::Animate => {
                 game.board.animate();
                 game.grade_animation.borrow_mut().animate();
-                game.grade.borrow_mut().decay();
+                game.clock.borrow_mut().animate();
+                let frame = game.clock.borrow().frame();
+                game.state.borrow_mut().advance(frame);
                 if !game.is_over() || game.board.is_animating() {
                     game.timer.borrow_mut().animate();
                 }
                 if !game.is_started && game.timer.borrow().animation.is_started() {
                     game.is_started = true;
-                    let row = game.next_row();
-                    game.board.feed(&row);
+                    game.apply(Action::Feed);
                 }
             }
 
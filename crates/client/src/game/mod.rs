@@ -3,15 +3,34 @@ mod board;
 mod hard;
 mod normal;
 mod online;
+mod practice;
+mod puzzle;
+mod spectate;
+
+use common::board::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
 
 pub use animation::*;
+pub use board::AnimatedBoard;
 pub use hard::GameHard;
 pub use normal::Game;
 pub use online::GameOnline;
+pub use online::Lobby;
+pub use online::MatchResult;
+pub use online::OpponentBoard;
 pub use online::OnlineGameAction;
+pub use practice::GamePractice;
+pub use practice::PracticeGameAction;
+pub use puzzle::GamePuzzle;
+pub use puzzle::PuzzleGameAction;
+pub use spectate::Spectate;
+pub use spectate::SpectateAction;
+
+pub const WIDTH: usize = DEFAULT_WIDTH;
+pub const HEIGHT: usize = DEFAULT_HEIGHT;
 
-pub const WIDTH: usize = 8;
-pub const HEIGHT: usize = 9;
+fn random_seed() -> u64 {
+    u64::from_be_bytes(js_sys::Math::random().to_be_bytes())
+}
 
 pub enum GameAction {
     Feed,
@@ -1,69 +1,38 @@
-use super::{animation::*, board::AnimatedBoard, GameAction, WIDTH};
+use super::{animation::*, board::AnimatedBoard, random_seed, GameAction, HEIGHT, WIDTH};
 use crate::animation::*;
-use common::board::CellType;
-use rand::prelude::*;
+use common::engine::{Event, GameState};
+use common::history::Operation;
+use common::replay::{Action, Mode, Replay, Rules};
+use common::scoring::ScoringRule;
 use std::cell::RefCell;
 use std::rc::Rc;
 use yew::Reducible;
 
-fn generate_x_pairs() -> Vec<(usize, usize)> {
-    (0..WIDTH)
-        .flat_map(|first| (first + 1..WIDTH).map(move |second| (first, second)))
-        .collect()
-}
-
-#[derive(Clone)]
-struct BombGenerator<T> {
-    shuffled: Vec<T>,
-    rng: StdRng,
-    generator: fn() -> Vec<T>,
-}
-
-impl<T> BombGenerator<T> {
-    fn new(generator: fn() -> Vec<T>) -> Self {
-        let random = js_sys::Math::random();
-        let rng = StdRng::seed_from_u64(u64::from_be_bytes(random.to_be_bytes()));
-        let mut generator = BombGenerator {
-            shuffled: Vec::new(),
-            rng,
-            generator,
-        };
-        generator.shuffle();
-        generator
-    }
-
-    fn shuffle(&mut self) {
-        let mut shuffled = (self.generator)();
-        shuffled.shuffle(&mut self.rng);
-        self.shuffled = shuffled;
-    }
-
-    fn next(&mut self) -> T {
-        self.shuffled.pop().unwrap_or_else(|| {
-            self.shuffle();
-            self.next()
-        })
-    }
-}
-
 #[derive(Clone)]
 pub struct Game {
     pub board: AnimatedBoard,
-    generator: BombGenerator<(usize, usize)>,
-    pub score: usize,
-    pub bombs_removed: usize,
-    pub bombs_limit: usize,
+    state: Rc<RefCell<GameState>>,
+    clock: Rc<RefCell<FloatAnimator<FrameCounter>>>,
     pub score_animator: Rc<RefCell<FloatAnimator<NumberAnimator>>>,
 }
 
 impl Game {
     pub fn new() -> Self {
-        Game {
-            board: AnimatedBoard::new(),
-            generator: BombGenerator::new(generate_x_pairs),
-            score: 0,
-            bombs_removed: 0,
+        let rules = Rules {
+            width: WIDTH,
+            height: HEIGHT,
             bombs_limit: 999,
+            ..Rules::default()
+        };
+        let state = GameState::new(Mode::Normal, random_seed(), rules);
+        // Starting from the engine's own board keeps settings like gravity and the blast shape
+        // in step with it.
+        let mut board = AnimatedBoard::new(WIDTH, HEIGHT);
+        board.board = state.board().clone();
+        Game {
+            board,
+            state: Rc::new(RefCell::new(state)),
+            clock: Rc::new(RefCell::new(FloatAnimator::new(Box::new(FrameCounter::new())))),
             score_animator: Rc::new(RefCell::new(FloatAnimator::new(Box::new(
                 NumberAnimator::new(0),
             )))),
@@ -71,16 +40,49 @@ impl Game {
     }
 
     pub fn is_over(&self) -> bool {
-        let reached_limit = self.bombs_limit <= self.bombs_removed;
-        self.board.is_filled() || reached_limit
+        self.state.borrow().is_over()
+    }
+
+    pub fn score(&self) -> usize {
+        self.state.borrow().score()
+    }
+
+    pub fn bombs_removed(&self) -> usize {
+        self.state.borrow().level()
     }
 
-    pub fn next_row(&mut self) -> [CellType; WIDTH] {
-        let bombs = self.generator.next();
-        let mut row = [CellType::Tile; WIDTH];
-        row[bombs.0] = CellType::Bomb;
-        row[bombs.1] = CellType::Bomb;
-        row
+    pub fn bombs_limit(&self) -> usize {
+        self.state.borrow().limit()
+    }
+
+    /// A new copy of the rule the game scores chains by.
+    pub fn scoring_rule(&self) -> Box<dyn ScoringRule> {
+        let state = self.state.borrow();
+        state.rules().scoring_rule(state.mode())
+    }
+
+    pub fn replay(&self) -> Replay {
+        self.state.borrow().replay().clone()
+    }
+
+    fn apply(&mut self, action: Action) {
+        let frame = self.clock.borrow().frame();
+        let events = self.state.borrow_mut().apply(frame, action);
+        for event in events {
+            match event {
+                Event::Removed { report, .. } => {
+                    self.board.play(&Operation::Remove(report));
+                    let gravity = Operation::gravity(&mut self.board.board);
+                    self.board.show(&gravity);
+                    self.score_animator
+                        .borrow_mut()
+                        .animation
+                        .set_target(self.score());
+                }
+                Event::Fed(row) => self.board.feed(&row),
+                _ => (),
+            }
+        }
     }
 }
 
@@ -91,38 +93,17 @@ impl Reducible for Game {
         let mut game = (*self).clone();
 
         match action {
-            GameAction::Remove(x, y) => {
-                if game.is_over() {
-                    return game.into();
-                }
-
-                let (removed_cells, removed_bombs) = game.board.remove(x, y);
-                if removed_cells > 0 {
-                    game.score += (removed_cells + 1) * removed_cells / 2;
-                    game.score_animator
-                        .borrow_mut()
-                        .animation
-                        .set_target(game.score);
-                    game.bombs_removed += removed_bombs;
-
-                    game.board.apply_gravity();
-                    let row = game.next_row();
-                    game.board.feed(&row);
-                }
-            }
-            GameAction::Feed => {
-                let row = game.next_row();
-                game.board.feed(&row);
-            }
+            GameAction::Remove(x, y) => game.apply(Action::Remove { x, y }),
+            GameAction::Feed => game.apply(Action::Feed),
             GameAction::Animate => {
                 self.board.animate();
                 self.score_animator.borrow_mut().animate();
+                self.clock.borrow_mut().animate();
             }
 
             GameAction::Retry => {
                 let mut game = Game::new();
-                let row = game.next_row();
-                game.board.feed(&row);
+                game.apply(Action::Feed);
                 return Rc::new(game);
             }
         }
@@ -1,25 +1,130 @@
-use super::{board::AnimatedBoard, WIDTH};
-use common::board::CellType;
+use super::{board::AnimatedBoard, HEIGHT, WIDTH};
+use common::board::{CellType, Grid};
+use common::garbage::{Attack, Targeting};
+use common::model::BoardSnapshot;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 use yew::Reducible;
 
+/// Where the player is before the match starts.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Lobby {
+    /// Choosing between the public queue and a private room. Holds the code of a room that could
+    /// not be joined.
+    Menu {
+        unavailable: Option<String>,
+    },
+    Queued,
+    Room {
+        code: String,
+        players: usize,
+        ready: usize,
+    },
+    Playing,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchResult {
+    Win,
+    Lose,
+    Draw,
+}
+
+/// What the compact grid shows of an opponent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpponentBoard {
+    pub heights: Vec<usize>,
+    pub place: Option<usize>,
+}
+
 #[derive(Clone)]
 pub struct GameOnline {
+    pub lobby: Lobby,
+    /// The player's number in the match, and how many players it started with.
+    pub player: usize,
+    pub players: usize,
+    pub opponents: BTreeMap<usize, OpponentBoard>,
+    /// The opponents' boards as they play them.
+    pub opponent_boards: BTreeMap<usize, AnimatedBoard>,
+    /// Where the player finished, once they topped out or won.
+    pub place: Option<usize>,
+    pub result: Option<MatchResult>,
+    /// The whole match ended, not only the player's part in it.
+    pub is_over: bool,
+    pub targeting: Targeting,
+    /// The opponent the last attack went to.
+    pub target: Option<usize>,
     pub board: AnimatedBoard,
+    /// As counted by the server.
+    pub score: usize,
+    /// Garbage the server will feed on the coming turns.
+    pub pending: Attack,
+    /// Garbage rows sent to the opponent so far.
+    pub sent: usize,
 }
 
 impl GameOnline {
     pub fn new() -> Self {
         GameOnline {
-            board: AnimatedBoard::new(),
+            lobby: Lobby::Menu { unavailable: None },
+            player: 0,
+            players: 0,
+            opponents: BTreeMap::new(),
+            opponent_boards: BTreeMap::new(),
+            place: None,
+            result: None,
+            is_over: false,
+            targeting: Targeting::default(),
+            target: None,
+            board: AnimatedBoard::new(WIDTH, HEIGHT),
+            score: 0,
+            pending: Attack::default(),
+            sent: 0,
         }
     }
+
+    /// The opponent whose board is shown live: the one the player last attacked while they are
+    /// still in, or else the first one still in.
+    pub fn shown_opponent(&self) -> Option<usize> {
+        let is_in = |id: &usize| self.opponents.get(id).is_some_and(|x| x.place.is_none());
+        self.target
+            .filter(is_in)
+            .or_else(|| self.opponents.keys().copied().find(is_in))
+    }
 }
 
 #[derive(Debug)]
 pub enum OnlineGameAction {
+    Lobby(Lobby),
+    /// Every board in the match is `width` by `height`.
+    Start {
+        player: usize,
+        players: usize,
+        width: usize,
+        height: usize,
+    },
+    /// Back in the match after reconnecting, which is as the boards show.
+    Resume {
+        player: usize,
+        players: usize,
+        boards: Vec<BoardSnapshot>,
+        score: usize,
+        pending: Attack,
+    },
     Remove(usize, usize),
-    Feed([CellType; WIDTH]),
+    Feed(Vec<CellType>),
+    Score(usize),
+    Garbage(Attack),
+    Sent(Attack, usize),
+    OpponentRemove(usize, usize, usize),
+    OpponentFeed(usize, Vec<CellType>),
+    Stack(usize, Vec<usize>),
+    Placement(usize, usize),
+    Targeting(Targeting),
+    Result(MatchResult),
+    GameOver,
+    /// Starts over in the queue after a match, keeping the targeting strategy.
+    Requeue,
     Animate,
 }
 
@@ -29,15 +134,125 @@ impl Reducible for GameOnline {
     fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
         let mut game = (*self).clone();
         match action {
+            OnlineGameAction::Lobby(lobby) => {
+                game.lobby = lobby;
+            }
+            OnlineGameAction::Start {
+                player,
+                players,
+                width,
+                height,
+            } => {
+                game.lobby = Lobby::Playing;
+                game.player = player;
+                game.players = players;
+                game.board = AnimatedBoard::new(width, height);
+                game.opponents = (0..players)
+                    .filter(|&id| id != player)
+                    .map(|id| (id, OpponentBoard::default()))
+                    .collect();
+                game.opponent_boards = game
+                    .opponents
+                    .keys()
+                    .map(|&id| (id, AnimatedBoard::new(width, height)))
+                    .collect();
+            }
+            OnlineGameAction::Resume {
+                player,
+                players,
+                boards,
+                score,
+                pending,
+            } => {
+                game.lobby = Lobby::Playing;
+                game.player = player;
+                game.players = players;
+                game.score = score;
+                game.pending = pending;
+                game.opponents.clear();
+                game.opponent_boards.clear();
+                for snapshot in boards {
+                    let mut board = AnimatedBoard::new(WIDTH, HEIGHT);
+                    if snapshot.player == player {
+                        game.place = snapshot.place;
+                        board.load(snapshot.board);
+                        game.board = board;
+                    } else {
+                        let opponent = OpponentBoard {
+                            heights: snapshot.board.column_heights(),
+                            place: snapshot.place,
+                        };
+                        board.load(snapshot.board);
+                        game.opponents.insert(snapshot.player, opponent);
+                        game.opponent_boards.insert(snapshot.player, board);
+                    }
+                }
+            }
             OnlineGameAction::Remove(x, y) => {
                 game.board.remove(x, y);
                 game.board.apply_gravity();
             }
             OnlineGameAction::Feed(row) => {
-                game.board.feed(&row);
+                if row.len() == game.board.board.width() {
+                    game.board.feed(&row);
+                }
+            }
+            OnlineGameAction::Score(score) => {
+                game.score = score;
+            }
+            OnlineGameAction::Garbage(pending) => {
+                game.pending = pending;
+            }
+            OnlineGameAction::Sent(attack, target) => {
+                game.sent += attack.total();
+                game.target = Some(target);
+            }
+            OnlineGameAction::OpponentRemove(player, x, y) => {
+                if let Some(board) = game.opponent_boards.get_mut(&player) {
+                    board.remove(x, y);
+                    board.apply_gravity();
+                }
+            }
+            OnlineGameAction::OpponentFeed(player, row) => {
+                if let Some(board) = game.opponent_boards.get_mut(&player) {
+                    if row.len() == board.board.width() {
+                        board.feed(&row);
+                    }
+                }
+            }
+            OnlineGameAction::Stack(player, heights) => {
+                if let Some(opponent) = game.opponents.get_mut(&player) {
+                    opponent.heights = heights;
+                }
+            }
+            OnlineGameAction::Placement(player, place) => {
+                if player == game.player {
+                    game.place = Some(place);
+                } else if let Some(opponent) = game.opponents.get_mut(&player) {
+                    opponent.place = Some(place);
+                }
+            }
+            OnlineGameAction::Targeting(targeting) => {
+                game.targeting = targeting;
+            }
+            OnlineGameAction::Result(result) => {
+                game.result = Some(result);
+            }
+            OnlineGameAction::GameOver => {
+                game.is_over = true;
+            }
+            OnlineGameAction::Requeue => {
+                game = GameOnline {
+                    lobby: Lobby::Queued,
+                    targeting: game.targeting,
+                    ..GameOnline::new()
+                };
             }
             OnlineGameAction::Animate => {
                 game.board.animate();
+                for board in game.opponent_boards.values() {
+                    board.animate();
+                }
             }
         }
 
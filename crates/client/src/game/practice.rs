@@ -0,0 +1,149 @@
+use super::{board::AnimatedBoard, random_seed, HEIGHT, WIDTH};
+use common::generator::{BagGenerator, RowGenerator};
+use common::history::{History, Operation};
+use common::replay::{Mode, Rules};
+use common::scoring::ScoringRule;
+use std::cell::RefCell;
+use std::rc::Rc;
+use yew::Reducible;
+
+/// Normal mode rules without a bomb limit, where every move can be undone and redone. Rows fed
+/// after an undo come from the generator again, so a different move meets different rows.
+#[derive(Clone)]
+pub struct GamePractice {
+    pub board: AnimatedBoard,
+    generator: BagGenerator,
+    history: Rc<RefCell<History>>,
+    rules: Rules,
+    scoring: Rc<RefCell<Box<dyn ScoringRule>>>,
+    pub score: usize,
+}
+
+impl GamePractice {
+    pub fn new() -> Self {
+        let rules = Rules::default();
+        let mut game = GamePractice {
+            board: AnimatedBoard::new(WIDTH, HEIGHT),
+            generator: BagGenerator::new(WIDTH, random_seed()),
+            history: Rc::new(RefCell::new(History::new())),
+            scoring: Rc::new(RefCell::new(rules.scoring_rule(Mode::Normal))),
+            rules,
+            score: 0,
+        };
+        let row = game.generator.next_row(2);
+        game.board.feed(&row);
+        game
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.board.is_filled()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.borrow().can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.borrow().can_redo()
+    }
+
+    /// A new copy of the rule the game scores chains by.
+    pub fn scoring_rule(&self) -> Box<dyn ScoringRule> {
+        self.rules.scoring_rule(Mode::Normal)
+    }
+
+    /// Scores the turns left after an undo or redo again, so rules that keep state between
+    /// chains pick up where those turns left them.
+    fn rescore(&mut self) {
+        let mut scoring = self.scoring.borrow_mut();
+        scoring.reset();
+        self.score = self
+            .history
+            .borrow()
+            .turns()
+            .flatten()
+            .map(|operation| match operation {
+                Operation::Remove(report) => scoring.score(report, 0),
+                _ => 0,
+            })
+            .sum();
+    }
+}
+
+pub enum PracticeGameAction {
+    Remove(usize, usize),
+    Undo,
+    Redo,
+    Animate,
+    Retry,
+}
+
+impl Reducible for GamePractice {
+    type Action = PracticeGameAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut game = (*self).clone();
+
+        match action {
+            PracticeGameAction::Remove(x, y) => {
+                if game.is_over() {
+                    return self;
+                }
+
+                let report = game.board.remove(x, y);
+                if report.is_empty() {
+                    return self;
+                }
+
+                let mut history = game.history.borrow_mut();
+                game.score += game.scoring.borrow_mut().score(&report, 0);
+                history.push(Operation::Remove(report));
+
+                let gravity = Operation::gravity(&mut game.board.board);
+                game.board.show(&gravity);
+                history.push(gravity);
+
+                let row = game.generator.next_row(2);
+                let feed = Operation::feed(&mut game.board.board, &row);
+                game.board.show(&feed);
+                history.push(feed);
+                history.end_turn();
+            }
+            PracticeGameAction::Undo => {
+                let mut history = game.history.borrow_mut();
+                if let Some(turn) = history.undo() {
+                    for operation in turn.iter().rev() {
+                        game.board.rewind(operation);
+                    }
+                }
+                drop(history);
+                game.rescore();
+            }
+            PracticeGameAction::Redo => {
+                let mut history = game.history.borrow_mut();
+                if let Some(turn) = history.redo() {
+                    for operation in turn {
+                        game.board.play(operation);
+                    }
+                }
+                drop(history);
+                game.rescore();
+            }
+            PracticeGameAction::Animate => {
+                self.board.animate();
+                return self;
+            }
+            PracticeGameAction::Retry => {
+                game.board.reset();
+                game.history = Rc::new(RefCell::new(History::new()));
+                game.scoring = Rc::new(RefCell::new(game.scoring_rule()));
+                game.score = 0;
+                let row = game.generator.next_row(2);
+                game.board.feed(&row);
+                return game.into();
+            }
+        }
+
+        game.into()
+    }
+}
@@ -0,0 +1,68 @@
+use super::board::AnimatedBoard;
+use common::board::Grid;
+use common::puzzle::{Puzzle, PuzzleGame, Status};
+use std::rc::Rc;
+use yew::Reducible;
+
+/// A puzzle on an animated board. The rules run on `game`, and the board replays its operations.
+#[derive(Clone)]
+pub struct GamePuzzle {
+    pub board: AnimatedBoard,
+    pub game: PuzzleGame,
+    pub puzzle: Rc<Puzzle>,
+}
+
+impl GamePuzzle {
+    /// `puzzle` must be valid.
+    pub fn new(puzzle: Rc<Puzzle>) -> Self {
+        let game = PuzzleGame::new(&puzzle).unwrap();
+        let mut board = AnimatedBoard::new(game.board.width(), game.board.height());
+        board.load(game.board.clone());
+        GamePuzzle {
+            board,
+            game,
+            puzzle,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        self.game.status()
+    }
+}
+
+pub enum PuzzleGameAction {
+    Remove(usize, usize),
+    Animate,
+    Retry,
+}
+
+impl Reducible for GamePuzzle {
+    type Action = PuzzleGameAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut game = (*self).clone();
+
+        match action {
+            PuzzleGameAction::Remove(x, y) => {
+                let operations = game.game.play(x, y);
+                if operations.is_empty() {
+                    return self;
+                }
+                for operation in &operations {
+                    game.board.play(operation);
+                }
+            }
+            PuzzleGameAction::Animate => {
+                self.board.animate();
+                return self;
+            }
+            PuzzleGameAction::Retry => {
+                game.game = PuzzleGame::new(&game.puzzle).unwrap();
+                game.board.reset();
+                game.board.load(game.game.board.clone());
+            }
+        }
+
+        game.into()
+    }
+}
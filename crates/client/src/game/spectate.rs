@@ -0,0 +1,106 @@
+use super::{board::AnimatedBoard, HEIGHT, WIDTH};
+use common::board::{CellType, Grid};
+use common::model::{BoardSnapshot, GameInfo};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use yew::Reducible;
+
+/// Watching a match other players are playing.
+#[derive(Clone)]
+pub struct Spectate {
+    /// The matches to choose from.
+    pub games: Vec<GameInfo>,
+    /// The match being watched.
+    pub watching: Option<usize>,
+    /// Every player's board in the match, by player number.
+    pub boards: BTreeMap<usize, AnimatedBoard>,
+    /// Where the players who are out finished.
+    pub places: BTreeMap<usize, usize>,
+    pub is_over: bool,
+}
+
+impl Spectate {
+    pub fn new() -> Self {
+        Spectate {
+            games: Vec::new(),
+            watching: None,
+            boards: BTreeMap::new(),
+            places: BTreeMap::new(),
+            is_over: false,
+        }
+    }
+}
+
+pub enum SpectateAction {
+    Games(Vec<GameInfo>),
+    Watch(usize),
+    Snapshot(Vec<BoardSnapshot>),
+    Remove(usize, usize, usize),
+    Feed(usize, Vec<CellType>),
+    Placement(usize, usize),
+    GameOver,
+    /// Goes back to the list of matches.
+    Back,
+    Animate,
+}
+
+impl Reducible for Spectate {
+    type Action = SpectateAction;
+
+    fn reduce(self: Rc<Self>, action: Self::Action) -> Rc<Self> {
+        let mut spectate = (*self).clone();
+        match action {
+            SpectateAction::Games(games) => {
+                spectate.games = games;
+                spectate.watching = None;
+            }
+            SpectateAction::Watch(game) => {
+                spectate.watching = Some(game);
+            }
+            SpectateAction::Snapshot(boards) => {
+                spectate.boards.clear();
+                spectate.places.clear();
+                for snapshot in boards {
+                    let mut board = AnimatedBoard::new(WIDTH, HEIGHT);
+                    board.load(snapshot.board);
+                    spectate.boards.insert(snapshot.player, board);
+                    if let Some(place) = snapshot.place {
+                        spectate.places.insert(snapshot.player, place);
+                    }
+                }
+            }
+            SpectateAction::Remove(player, x, y) => {
+                if let Some(board) = spectate.boards.get_mut(&player) {
+                    board.remove(x, y);
+                    board.apply_gravity();
+                }
+            }
+            SpectateAction::Feed(player, row) => {
+                if let Some(board) = spectate.boards.get_mut(&player) {
+                    if row.len() == board.board.width() {
+                        board.feed(&row);
+                    }
+                }
+            }
+            SpectateAction::Placement(player, place) => {
+                spectate.places.insert(player, place);
+            }
+            SpectateAction::GameOver => {
+                spectate.is_over = true;
+            }
+            SpectateAction::Back => {
+                spectate = Spectate {
+                    games: spectate.games,
+                    ..Spectate::new()
+                };
+            }
+            SpectateAction::Animate => {
+                for board in spectate.boards.values() {
+                    board.animate();
+                }
+            }
+        }
+
+        spectate.into()
+    }
+}
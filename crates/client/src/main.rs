@@ -1,6 +1,11 @@
+// `html!` expands component props into statements that clippy flags.
+#![allow(clippy::unnecessary_operation)]
+
 mod animation;
 mod components;
 mod game;
+mod puzzle;
+mod replay;
 mod websocket;
 
 fn main() {
@@ -0,0 +1,63 @@
+use common::puzzle::PuzzlePack;
+use std::collections::BTreeSet;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+/// Lists the pack files in `/puzzles`, in the order they are shown.
+const INDEX: &str = "/puzzles/index.json";
+const COMPLETED_KEY: &str = "exploded.puzzles.completed";
+
+async fn fetch_text(url: &str) -> Option<String> {
+    let window = web_sys::window()?;
+    let response = JsFuture::from(window.fetch_with_str(url)).await.ok()?;
+    let response: web_sys::Response = response.dyn_into().ok()?;
+    if !response.ok() {
+        return None;
+    }
+    JsFuture::from(response.text().ok()?)
+        .await
+        .ok()?
+        .as_string()
+}
+
+/// Fetches every bundled pack. Packs that fail to load and puzzles that fail validation are left
+/// out rather than breaking the menu.
+pub async fn load_packs() -> Vec<PuzzlePack> {
+    let files: Vec<String> = match fetch_text(INDEX).await {
+        Some(index) => serde_json::from_str(&index).unwrap_or_default(),
+        None => return Vec::new(),
+    };
+
+    let mut packs = Vec::new();
+    for file in files {
+        let pack = fetch_text(&format!("/puzzles/{file}"))
+            .await
+            .and_then(|json| serde_json::from_str::<PuzzlePack>(&json).ok());
+        if let Some(mut pack) = pack {
+            pack.puzzles.retain(|puzzle| puzzle.validate().is_ok());
+            packs.push(pack);
+        }
+    }
+    packs
+}
+
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// Ids of the puzzles solved on this browser.
+pub fn completed() -> BTreeSet<String> {
+    storage()
+        .and_then(|storage| storage.get_item(COMPLETED_KEY).ok()?)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn mark_completed(id: &str) {
+    let mut ids = completed();
+    if ids.insert(id.to_string()) {
+        if let Some(storage) = storage() {
+            let _ = storage.set_item(COMPLETED_KEY, &serde_json::to_string(&ids).unwrap());
+        }
+    }
+}
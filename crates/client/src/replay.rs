@@ -0,0 +1,17 @@
+use common::replay::Replay;
+use wasm_bindgen::JsCast;
+
+/// Saves the replay as a JSON file through a temporary download link.
+pub fn download(replay: &Replay) {
+    let json = serde_json::to_string(replay).unwrap();
+    let href = format!(
+        "data:application/json;charset=utf-8,{}",
+        js_sys::encode_uri_component(&json)
+    );
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let link = document.create_element("a").unwrap();
+    link.set_attribute("href", &href).unwrap();
+    link.set_attribute("download", "exploded-replay.json").unwrap();
+    link.dyn_into::<web_sys::HtmlElement>().unwrap().click();
+}
@@ -1,39 +1,125 @@
 use common::model::{RequestMessage, ResponseMessage};
 use futures::{
     channel::mpsc::{channel, Sender},
-    SinkExt, StreamExt,
+    future, select, SinkExt, StreamExt,
 };
-use gloo_net::websocket::{futures::WebSocket, Message};
+use gloo_net::websocket::{futures::WebSocket, Message, State};
 use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use wasm_bindgen_futures::spawn_local;
 use yew_agent::{Agent, AgentLink, Context, HandlerId};
 
+const URL: &str = "ws://localhost:9000/ws";
+/// The wait before reconnecting, doubled after every failed attempt up to the maximum.
+const INITIAL_BACKOFF_MILLIS: i32 = 500;
+const MAX_BACKOFF_MILLIS: i32 = 16_000;
+
+async fn sleep(millis: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, millis)
+            .unwrap();
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+/// Opens a connection and waits for it to be established.
+async fn connect() -> Option<WebSocket> {
+    let mut ws = WebSocket::open(URL).ok()?;
+    // The socket is ready to send once it opens or fails to.
+    let _ = future::poll_fn(|cx| ws.poll_ready_unpin(cx)).await;
+    matches!(ws.state(), State::Open).then_some(ws)
+}
+
 struct WebsocketHandler {
     req_tx: Sender<RequestMessage>,
 }
 
 impl WebsocketHandler {
+    /// Keeps a connection open, reconnecting with backoff whenever it drops. The session of the
+    /// match being played is remembered so a new connection resumes it. Outside of a match, the
+    /// queue, room or match being watched is asked for again instead.
     fn new(mut callback: impl FnMut(ResponseMessage) + 'static) -> Self {
         let (tx, mut rx) = channel(0);
 
-        let ws = WebSocket::open("ws://localhost:9000/ws").unwrap();
-        let (mut write, mut read) = ws.split();
         spawn_local(async move {
-            while let Some(msg) = rx.next().await {
-                write
-                    .send(Message::Text(serde_json::to_string(&msg).unwrap()))
-                    .await
-                    .unwrap();
-            }
-        });
-        spawn_local(async move {
-            while let Some(msg) = read.next().await {
-                if let Ok(Message::Text(text)) = msg {
-                    if let Ok(msg) = serde_json::from_str(&text) {
-                        callback(msg);
+            let mut session: Option<String> = None;
+            let mut lobby: Option<RequestMessage> = None;
+            let mut backoff = INITIAL_BACKOFF_MILLIS;
+            loop {
+                if let Some(ws) = connect().await {
+                    backoff = INITIAL_BACKOFF_MILLIS;
+                    let (mut write, read) = ws.split();
+                    let mut read = read.fuse();
+                    let rejoin = match session.clone() {
+                        Some(session) => Some(RequestMessage::Resume { session }),
+                        None => lobby.clone(),
+                    };
+                    if let Some(msg) = rejoin {
+                        let _ = write
+                            .send(Message::Text(serde_json::to_string(&msg).unwrap()))
+                            .await;
+                    }
+                    loop {
+                        select! {
+                            msg = rx.next() => {
+                                let Some(msg) = msg else {
+                                    return;
+                                };
+                                match &msg {
+                                    RequestMessage::Join
+                                    | RequestMessage::CreateRoom
+                                    | RequestMessage::JoinRoom { .. }
+                                    | RequestMessage::Spectate { .. } => {
+                                        session = None;
+                                        lobby = Some(msg.clone());
+                                    }
+                                    RequestMessage::Leave => {
+                                        session = None;
+                                        lobby = None;
+                                    }
+                                    _ => (),
+                                }
+                                let text = serde_json::to_string(&msg).unwrap();
+                                if write.send(Message::Text(text)).await.is_err() {
+                                    break;
+                                }
+                            }
+                            msg = read.next() => match msg {
+                                Some(Ok(Message::Text(text))) => {
+                                    if let Ok(msg) = serde_json::from_str(&text) {
+                                        match &msg {
+                                            ResponseMessage::Ready { session: new, .. } => {
+                                                session = Some(new.clone());
+                                                lobby = None;
+                                            }
+                                            // A new room's code is only known here, and the same
+                                            // room is rejoined after a reconnect.
+                                            ResponseMessage::Room { code, .. } => {
+                                                lobby = Some(RequestMessage::JoinRoom {
+                                                    code: code.clone(),
+                                                });
+                                            }
+                                            ResponseMessage::RoomUnavailable { .. } => lobby = None,
+                                            ResponseMessage::GameOver
+                                            | ResponseMessage::SessionExpired => {
+                                                session = None;
+                                                lobby = None;
+                                            }
+                                            _ => (),
+                                        }
+                                        callback(msg);
+                                    }
+                                }
+                                Some(Ok(_)) => (),
+                                Some(Err(_)) | None => break,
+                            },
+                        }
                     }
                 }
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF_MILLIS);
             }
         });
 
@@ -0,0 +1,80 @@
+use common::bitboard::BitBoard;
+use common::board::{CellType, DynamicBoard, Grid};
+use common::rng::Rng;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn random_row(rng: &mut Rng, width: usize) -> Vec<CellType> {
+    (0..width)
+        .map(|_| CellType::ALL[rng.below(CellType::ALL.len() as u64) as usize])
+        .collect()
+}
+
+/// A default-sized board eight rows deep and the rows to feed it afterwards.
+fn setup() -> (DynamicBoard, Vec<Vec<CellType>>) {
+    let mut rng = Rng::new(0);
+    let mut board = DynamicBoard::new(8, 9);
+    for _ in 0..8 {
+        board.feed(&random_row(&mut rng, 8));
+    }
+    let rows = (0..64).map(|_| random_row(&mut rng, 8)).collect();
+    (board, rows)
+}
+
+fn bench_turns(c: &mut Criterion) {
+    let (board, rows) = setup();
+    let bits = BitBoard::from(&board);
+    let mut group = c.benchmark_group("turn");
+
+    group.bench_function("dynamic", |b| {
+        b.iter(|| {
+            let mut board = board.clone();
+            for (i, row) in rows.iter().enumerate() {
+                board.remove(i % 8, 8 - i % 3);
+                board.apply_gravity();
+                board.feed(row);
+            }
+            black_box(board)
+        })
+    });
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            let mut bits = bits.clone();
+            for (i, row) in rows.iter().enumerate() {
+                bits.remove(i % 8, 8 - i % 3);
+                bits.apply_gravity();
+                bits.feed(row);
+            }
+            black_box(bits)
+        })
+    });
+    group.finish();
+}
+
+fn bench_remove(c: &mut Criterion) {
+    let (board, _) = setup();
+    let bits = BitBoard::from(&board);
+    let mut group = c.benchmark_group("remove_all");
+
+    group.bench_function("dynamic", |b| {
+        b.iter(|| {
+            for x in 0..8 {
+                for y in 1..9 {
+                    black_box(board.clone().remove(x, y));
+                }
+            }
+        })
+    });
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            for x in 0..8 {
+                for y in 1..9 {
+                    black_box(bits.clone().remove(x, y));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_turns, bench_remove);
+criterion_main!(benches);
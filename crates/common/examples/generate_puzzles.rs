@@ -0,0 +1,32 @@
+//! Prints a pack of generated puzzles as JSON.
+//!
+//! cargo run --release -p common --example generate_puzzles -- [seed] [count] [moves]
+
+use common::board::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use common::puzzle::PuzzlePack;
+use common::puzzle_generator::PuzzleGenerator;
+
+fn main() {
+    let args: Vec<u64> = std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse().expect("arguments must be numbers"))
+        .collect();
+    let seed = args.first().copied().unwrap_or(0);
+    let count = args.get(1).copied().unwrap_or(10) as usize;
+
+    let mut generator = PuzzleGenerator::new(DEFAULT_WIDTH, DEFAULT_HEIGHT);
+    generator.moves = args.get(2).copied().unwrap_or(2) as usize;
+    generator.rows = 5;
+    generator.armored = 1;
+
+    let mut puzzles = generator.generate(seed, count, count * 100);
+    puzzles.sort_by_key(|puzzle| puzzle.difficulty);
+    for (i, puzzle) in puzzles.iter_mut().enumerate() {
+        puzzle.title = format!("Generated {}", i + 1);
+    }
+    let pack = PuzzlePack {
+        name: "Generated".to_string(),
+        puzzles,
+    };
+    println!("{}", serde_json::to_string_pretty(&pack).unwrap());
+}
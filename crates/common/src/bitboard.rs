@@ -0,0 +1,382 @@
+use crate::blast::BlastShape;
+use crate::board::{CellType, DynamicBoard, Gravity, Grid};
+use crate::zobrist;
+use std::fmt;
+
+const KINDS: usize = CellType::ALL.len();
+const MAX_SIZE: usize = 64;
+
+const CROSS_OFFSETS: [(isize, isize); 8] = [
+    (1, 0),
+    (2, 0),
+    (0, 1),
+    (0, 2),
+    (-1, 0),
+    (-2, 0),
+    (0, -1),
+    (0, -2),
+];
+
+fn kind(cell_type: CellType) -> usize {
+    cell_type as usize
+}
+
+/// Moves every bit of `mask` down the column by `dy` rows, up when negative.
+fn shift(mask: u64, dy: isize) -> u64 {
+    if dy >= 0 {
+        mask.checked_shl(dy as u32).unwrap_or(0)
+    } else {
+        mask.checked_shr(dy.unsigned_abs() as u32).unwrap_or(0)
+    }
+}
+
+/// What a single `BitBoard::remove` did, without per-cell detail.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BitReport {
+    pub removed: usize,
+    pub bombs: usize,
+    pub damaged: usize,
+    pub depth: usize,
+}
+
+impl BitReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed == 0 && self.damaged == 0
+    }
+}
+
+/// A board packed into one bitmask per cell type and column, bit `y` standing for row `y`. It
+/// follows the `Grid` rules without cell ids and without allocating per move, for search and
+/// verification that run many simulations. Both dimensions are limited to 64.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitBoard {
+    width: usize,
+    height: usize,
+    columns: Vec<[u64; KINDS]>,
+    blast_shape: BlastShape,
+    offsets: Vec<(isize, isize)>,
+    gravity: Gravity,
+}
+
+impl BitBoard {
+    pub fn new(width: usize, height: usize, blast_shape: BlastShape) -> Self {
+        assert!(width <= MAX_SIZE && height <= MAX_SIZE);
+        BitBoard {
+            width,
+            height,
+            columns: vec![[0; KINDS]; width],
+            offsets: blast_shape.offsets(),
+            blast_shape,
+            gravity: Gravity::default(),
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn blast_shape(&self) -> &BlastShape {
+        &self.blast_shape
+    }
+
+    pub fn gravity(&self) -> Gravity {
+        self.gravity
+    }
+
+    pub fn set_gravity(&mut self, gravity: Gravity) {
+        self.gravity = gravity;
+    }
+
+    fn full(&self) -> u64 {
+        u64::MAX
+            .checked_shr((MAX_SIZE - self.height) as u32)
+            .unwrap_or(0)
+    }
+
+    /// The rows of column `x` that hold a cell.
+    pub fn occupied(&self, x: usize) -> u64 {
+        self.columns[x].iter().fold(0, |acc, mask| acc | mask)
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<CellType> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        CellType::ALL
+            .into_iter()
+            .find(|&cell_type| self.columns[x][kind(cell_type)] >> y & 1 == 1)
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, cell_type: Option<CellType>) {
+        for mask in self.columns[x].iter_mut() {
+            *mask &= !(1 << y);
+        }
+        if let Some(cell_type) = cell_type {
+            self.columns[x][kind(cell_type)] |= 1 << y;
+        }
+    }
+
+    pub fn is_filled(&self) -> bool {
+        (0..self.width).any(|x| self.occupied(x) & 1 == 1)
+    }
+
+    /// Detonates the cell at `(x, y)` with the same rules as `Grid::remove`, one wave at a time.
+    pub fn remove(&mut self, x: usize, y: usize) -> BitReport {
+        let (width, full) = (self.width, self.full());
+        let mut frontier = [0u64; MAX_SIZE];
+        let mut next = [0u64; MAX_SIZE];
+        let mut hit = [0u64; MAX_SIZE];
+        let mut report = BitReport::default();
+        if x >= width || y >= self.height {
+            return report;
+        }
+        frontier[x] = 1 << y;
+
+        for wave in 0.. {
+            let mut reached = false;
+            let mut row_bombs = 0;
+            for cx in 0..width {
+                let targets = frontier[cx] & !hit[cx] & full;
+                frontier[cx] = 0;
+                hit[cx] |= targets;
+                let targets = targets & self.occupied(cx);
+                if targets == 0 {
+                    continue;
+                }
+                reached = true;
+
+                let column = &mut self.columns[cx];
+                let armored = column[kind(CellType::Armored)] & targets;
+                column[kind(CellType::Armored)] &= !armored;
+                column[kind(CellType::Tile)] |= armored;
+                report.damaged += armored.count_ones() as usize;
+
+                let removed = targets & !armored;
+                if removed != 0 {
+                    report.depth = wave;
+                }
+                report.bombs += (removed & !column[kind(CellType::Tile)]).count_ones() as usize;
+                let bombs = column[kind(CellType::Bomb)] & removed;
+                let crosses = column[kind(CellType::CrossBomb)] & removed;
+                row_bombs |= column[kind(CellType::RowBomb)] & removed;
+                if column[kind(CellType::ColumnBomb)] & removed != 0 {
+                    next[cx] |= full;
+                }
+                for mask in column.iter_mut() {
+                    *mask &= !removed;
+                }
+                report.removed += removed.count_ones() as usize;
+
+                for (sources, offsets) in [(bombs, &self.offsets[..]), (crosses, &CROSS_OFFSETS)] {
+                    if sources == 0 {
+                        continue;
+                    }
+                    for &(dx, dy) in offsets {
+                        let tx = cx as isize + dx;
+                        if tx >= 0 && (tx as usize) < width {
+                            next[tx as usize] |= shift(sources, dy) & full;
+                        }
+                    }
+                }
+            }
+
+            if !reached {
+                break;
+            }
+            if row_bombs != 0 {
+                for mask in next[..width].iter_mut() {
+                    *mask |= row_bombs;
+                }
+            }
+            std::mem::swap(&mut frontier, &mut next);
+        }
+
+        report
+    }
+
+    pub fn apply_gravity(&mut self) {
+        let height = self.height;
+        for column in self.columns.iter_mut() {
+            let mut occupied = column.iter().fold(0, |acc, mask| acc | mask);
+            let mut packed = [0u64; KINDS];
+            let mut target = height;
+            while occupied != 0 {
+                let y = MAX_SIZE - 1 - occupied.leading_zeros() as usize;
+                occupied &= !(1 << y);
+                target -= 1;
+                for (packed, mask) in packed.iter_mut().zip(column.iter()) {
+                    *packed |= (mask >> y & 1) << target;
+                }
+            }
+            *column = packed;
+        }
+
+        if self.gravity == Gravity::Collapse {
+            self.columns
+                .retain(|column| column.iter().any(|&mask| mask != 0));
+            self.columns.resize(self.width, [0; KINDS]);
+        }
+    }
+
+    pub fn feed(&mut self, row: &[CellType]) {
+        assert_eq!(row.len(), self.width);
+        let bottom = 1 << (self.height - 1);
+        for (column, &cell_type) in self.columns.iter_mut().zip(row) {
+            for mask in column.iter_mut() {
+                *mask >>= 1;
+            }
+            column[kind(cell_type)] |= bottom;
+        }
+    }
+
+    /// Equal to `Grid::position_hash` of the same layout.
+    pub fn position_hash(&self) -> u64 {
+        let mut hash = 0;
+        for (x, column) in self.columns.iter().enumerate() {
+            for cell_type in CellType::ALL {
+                let mut mask = column[kind(cell_type)];
+                while mask != 0 {
+                    let y = mask.trailing_zeros() as usize;
+                    mask &= mask - 1;
+                    hash ^= zobrist::key(x, y, cell_type);
+                }
+            }
+        }
+        hash
+    }
+
+    pub fn to_notation(&self) -> String {
+        (0..self.height)
+            .map(|y| {
+                (0..self.width)
+                    .map(|x| self.get(x, y).map_or('.', CellType::to_char))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Packs the layout of any grid. Cell ids are dropped.
+    pub fn from_grid<G: Grid + ?Sized>(grid: &G) -> Self {
+        let mut board = BitBoard::new(grid.width(), grid.height(), grid.blast_shape().clone());
+        board.gravity = grid.gravity();
+        for x in 0..grid.width() {
+            for y in 0..grid.height() {
+                board.set(x, y, grid.get(x, y).map(|cell| cell.cell_type));
+            }
+        }
+        board
+    }
+}
+
+impl From<&DynamicBoard> for BitBoard {
+    fn from(board: &DynamicBoard) -> Self {
+        BitBoard::from_grid(board)
+    }
+}
+
+/// Unpacks the layout, assigning fresh ids bottom row first like the text notation does.
+impl From<&BitBoard> for DynamicBoard {
+    fn from(bits: &BitBoard) -> Self {
+        let mut board = DynamicBoard::new(bits.width, bits.height);
+        board.blast_shape = bits.blast_shape.clone();
+        board.gravity = bits.gravity;
+        for y in (0..bits.height).rev() {
+            for x in 0..bits.width {
+                board.column_mut(x)[y] = bits
+                    .get(x, y)
+                    .map(|cell_type| board.generate_cell(cell_type));
+            }
+        }
+        board
+    }
+}
+
+impl fmt::Display for BitBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_notation())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+
+    fn random_cell_type(rng: &mut Rng) -> CellType {
+        CellType::ALL[rng.below(CellType::ALL.len() as u64) as usize]
+    }
+
+    #[test]
+    fn test_conversion() {
+        let board: DynamicBoard = "
+            .#..
+            #@+.
+            *#|-
+        "
+        .parse()
+        .unwrap();
+        let bits = BitBoard::from(&board);
+        assert_eq!(bits.to_string(), board.to_string());
+        assert_eq!(bits.get(1, 1), Some(CellType::Armored));
+        assert_eq!(bits.get(3, 0), None);
+        assert_eq!(bits.get(3, 2), Some(CellType::RowBomb));
+
+        let restored = DynamicBoard::from(&bits);
+        assert_eq!(restored.cells(), board.cells());
+    }
+
+    #[test]
+    fn test_matches_dynamic_board() {
+        for seed in 0..200 {
+            let mut rng = Rng::new(seed);
+            let (width, height) = (1 + rng.below(8) as usize, 1 + rng.below(10) as usize);
+            let mut board = DynamicBoard::new(width, height);
+            board.blast_shape = match rng.below(3) {
+                0 => BlastShape::King,
+                1 => BlastShape::Knight,
+                _ => BlastShape::Diamond(2),
+            };
+            if rng.below(2) == 0 {
+                board.gravity = Gravity::Collapse;
+            }
+            let mut bits = BitBoard::from(&board);
+
+            for step in 0..40 {
+                match rng.below(3) {
+                    0 => {
+                        let x = rng.below(width as u64) as usize;
+                        let y = rng.below(height as u64) as usize;
+                        let report = board.remove(x, y);
+                        let expected = BitReport {
+                            removed: report.removed_count(),
+                            bombs: report.bombs(),
+                            damaged: report.damaged.len(),
+                            depth: report.depth(),
+                        };
+                        assert_eq!(bits.remove(x, y), expected, "seed {seed}, step {step}");
+                    }
+                    1 => {
+                        board.apply_gravity();
+                        bits.apply_gravity();
+                    }
+                    _ => {
+                        let row: Vec<_> = (0..width).map(|_| random_cell_type(&mut rng)).collect();
+                        board.feed(&row);
+                        bits.feed(&row);
+                    }
+                }
+                assert_eq!(
+                    bits.to_notation(),
+                    board.to_notation(),
+                    "seed {seed}, step {step}"
+                );
+                assert_eq!(bits.is_filled(), board.is_filled());
+            }
+        }
+    }
+}
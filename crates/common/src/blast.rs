@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+/// The cells a bomb reaches, relative to the bomb itself.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlastShape {
+    /// The 8 surrounding cells.
+    #[default]
+    King,
+    /// The 4 orthogonally adjacent cells.
+    Orthogonal,
+    /// Every cell within the given Manhattan distance.
+    Diamond(usize),
+    /// Up to the given number of cells in each orthogonal direction.
+    Plus(usize),
+    /// The 8 cells a chess knight could jump to.
+    Knight,
+    Custom(Vec<(isize, isize)>),
+}
+
+impl BlastShape {
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        match self {
+            BlastShape::King => (-1..=1)
+                .flat_map(|x| (-1..=1).map(move |y| (x, y)))
+                .filter(|&x| x != (0, 0))
+                .collect(),
+            BlastShape::Orthogonal => BlastShape::Plus(1).offsets(),
+            &BlastShape::Diamond(radius) => {
+                let radius = radius as isize;
+                (-radius..=radius)
+                    .flat_map(|x| {
+                        let rest = radius - x.abs();
+                        (-rest..=rest).map(move |y| (x, y))
+                    })
+                    .filter(|&x| x != (0, 0))
+                    .collect()
+            }
+            &BlastShape::Plus(length) => [(1, 0), (0, 1), (-1, 0), (0, -1)]
+                .into_iter()
+                .flat_map(|(x, y)| (1..=length as isize).map(move |i| (x * i, y * i)))
+                .collect(),
+            BlastShape::Knight => vec![
+                (-2, -1),
+                (-2, 1),
+                (-1, -2),
+                (-1, 2),
+                (1, -2),
+                (1, 2),
+                (2, -1),
+                (2, 1),
+            ],
+            BlastShape::Custom(offsets) => offsets.clone(),
+        }
+    }
+
+    /// The cells reached by a bomb at `(x, y)` that lie on a `width` x `height` board.
+    pub fn cells(&self, x: usize, y: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let x = x as isize;
+        let y = y as isize;
+        self.offsets()
+            .into_iter()
+            .flat_map(|(vx, vy)| {
+                (x + vx)
+                    .try_into()
+                    .and_then(|x| (y + vy).try_into().map(|y| (x, y)))
+            })
+            .filter(|&(x, y)| x < width && y < height)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_king() {
+        assert_eq!(
+            BlastShape::King.cells(1, 1, 3, 3),
+            vec![
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 2),
+                (2, 0),
+                (2, 1),
+                (2, 2)
+            ]
+        );
+
+        assert_eq!(
+            BlastShape::King.cells(0, 0, 3, 3),
+            vec![(0, 1), (1, 0), (1, 1)]
+        );
+
+        assert_eq!(
+            BlastShape::King.cells(2, 2, 3, 3),
+            vec![(1, 1), (1, 2), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_shapes() {
+        fn sorted(mut cells: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+            cells.sort();
+            cells
+        }
+
+        assert_eq!(
+            sorted(BlastShape::Orthogonal.cells(1, 1, 3, 3)),
+            vec![(0, 1), (1, 0), (1, 2), (2, 1)]
+        );
+        assert_eq!(BlastShape::Diamond(2).offsets().len(), 12);
+        assert_eq!(
+            sorted(BlastShape::Diamond(2).cells(0, 0, 5, 5)),
+            vec![(0, 1), (0, 2), (1, 0), (1, 1), (2, 0)]
+        );
+        assert_eq!(
+            sorted(BlastShape::Plus(2).cells(1, 0, 5, 5)),
+            vec![(0, 0), (1, 1), (1, 2), (2, 0), (3, 0)]
+        );
+        assert_eq!(
+            sorted(BlastShape::Knight.cells(0, 0, 5, 5)),
+            vec![(1, 2), (2, 1)]
+        );
+        assert_eq!(
+            BlastShape::Custom(vec![(0, -1), (0, -2)]).cells(3, 1, 5, 5),
+            vec![(3, 0)]
+        );
+    }
+}
@@ -1,31 +1,84 @@
+use crate::blast::BlastShape;
+use crate::chain::{ChainReport, RemovedCell};
+use crate::zobrist;
+use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
 
-fn adjacent_cells(
+pub(crate) fn blast_area(
+    cell_type: CellType,
+    topology: &BlastShape,
     x: usize,
     y: usize,
     width: usize,
     height: usize,
-) -> impl Iterator<Item = (usize, usize)> {
-    let x = x as isize;
-    let y = y as isize;
-    (-1..=1)
-        .flat_map(|x| (-1..=1).map(move |y| (x, y)))
-        .filter(|&x| x != (0, 0))
-        .flat_map(move |(vx, vy)| {
-            (x + vx)
-                .try_into()
-                .and_then(|x| (y + vy).try_into().map(|y| (x, y)))
-        })
-        .filter(move |&(x, y)| x < width && y < height)
+) -> Vec<(usize, usize)> {
+    match cell_type {
+        CellType::Tile | CellType::Armored => Vec::new(),
+        CellType::Bomb => topology.cells(x, y, width, height),
+        CellType::RowBomb => (0..width).filter(|&i| i != x).map(|i| (i, y)).collect(),
+        CellType::ColumnBomb => (0..height).filter(|&i| i != y).map(|i| (x, i)).collect(),
+        CellType::CrossBomb => BlastShape::Plus(2).cells(x, y, width, height),
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum CellType {
     Tile,
     Bomb,
+    /// A tile that turns into a plain `Tile` the first time it is hit.
+    Armored,
+    /// Blasts every cell in its row.
+    RowBomb,
+    /// Blasts every cell in its column.
+    ColumnBomb,
+    /// Blasts up to two cells in each orthogonal direction, whatever the board's topology.
+    CrossBomb,
+}
+
+impl CellType {
+    pub const ALL: [CellType; 6] = [
+        CellType::Tile,
+        CellType::Bomb,
+        CellType::Armored,
+        CellType::RowBomb,
+        CellType::ColumnBomb,
+        CellType::CrossBomb,
+    ];
+
+    pub fn is_bomb(self) -> bool {
+        matches!(
+            self,
+            CellType::Bomb | CellType::RowBomb | CellType::ColumnBomb | CellType::CrossBomb
+        )
+    }
+
+    pub fn to_char(self) -> char {
+        match self {
+            CellType::Tile => '#',
+            CellType::Bomb => '*',
+            CellType::Armored => '@',
+            CellType::RowBomb => '-',
+            CellType::ColumnBomb => '|',
+            CellType::CrossBomb => '+',
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            '#' => Some(CellType::Tile),
+            '*' => Some(CellType::Bomb),
+            '@' => Some(CellType::Armored),
+            '-' => Some(CellType::RowBomb),
+            '|' => Some(CellType::ColumnBomb),
+            '+' => Some(CellType::CrossBomb),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Cell {
     pub id: usize,
     pub cell_type: CellType,
@@ -37,87 +90,564 @@ impl Cell {
     }
 }
 
-#[derive(Clone)]
-pub struct Board<const WIDTH: usize, const HEIGHT: usize> {
-    pub cells: [[Option<Cell>; HEIGHT]; WIDTH],
-    generated_cells: usize,
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BoardError {
+    UnknownCell(char),
+    RaggedRows,
+    DimensionMismatch {
+        expected: (usize, usize),
+        found: (usize, usize),
+    },
+    /// The board has no rows or no columns.
+    Empty,
 }
 
-impl<const WIDTH: usize, const HEIGHT: usize> Default for Board<WIDTH, HEIGHT> {
-    fn default() -> Self {
-        Self::new()
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardError::UnknownCell(c) => write!(f, "unknown cell {c:?}"),
+            BoardError::RaggedRows => write!(f, "rows or columns differ in length"),
+            BoardError::DimensionMismatch { expected, found } => write!(
+                f,
+                "expected a {}x{} board, found {}x{}",
+                expected.0, expected.1, found.0, found.1
+            ),
+            BoardError::Empty => write!(f, "a board needs at least one row and one column"),
+        }
     }
 }
 
-impl<const WIDTH: usize, const HEIGHT: usize> Board<WIDTH, HEIGHT> {
-    pub fn new() -> Self {
-        Board {
-            cells: [[None; HEIGHT]; WIDTH],
-            generated_cells: 0,
+impl std::error::Error for BoardError {}
+
+/// One distinct outcome of detonating a cell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    /// The first trigger in column order, a representative of the whole class.
+    pub x: usize,
+    pub y: usize,
+    /// Every cell whose detonation has this outcome.
+    pub triggers: Vec<(usize, usize)>,
+    /// Sorted positions of the cells the chain removes.
+    pub removed: Vec<(usize, usize)>,
+    /// Sorted positions of the armored cells the chain only damages.
+    pub damaged: Vec<(usize, usize)>,
+}
+
+/// The cells a chain started at `(x, y)` removes and damages, found without touching the grid.
+#[allow(clippy::type_complexity)]
+fn blast_closure<G: Grid + ?Sized>(
+    grid: &G,
+    x: usize,
+    y: usize,
+) -> (Vec<(usize, usize)>, Vec<(usize, usize)>) {
+    let (width, height) = (grid.width(), grid.height());
+    let mut hit = vec![false; width * height];
+    let mut stack = vec![(x, y)];
+    let (mut removed, mut damaged) = (Vec::new(), Vec::new());
+
+    while let Some((x, y)) = stack.pop() {
+        if x >= width || y >= height || hit[x * height + y] {
+            continue;
         }
+        hit[x * height + y] = true;
+
+        match grid.get(x, y) {
+            Some(Cell {
+                cell_type: CellType::Armored,
+                ..
+            }) => damaged.push((x, y)),
+            Some(Cell { cell_type, .. }) => {
+                removed.push((x, y));
+                stack.extend(blast_area(
+                    cell_type,
+                    grid.blast_shape(),
+                    x,
+                    y,
+                    width,
+                    height,
+                ));
+            }
+            None => (),
+        }
+    }
+
+    removed.sort_unstable();
+    damaged.sort_unstable();
+    (removed, damaged)
+}
+
+pub const DEFAULT_WIDTH: usize = 8;
+pub const DEFAULT_HEIGHT: usize = 9;
+
+/// What happens to the cells left above a removed chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gravity {
+    /// Cells fall down their column.
+    #[default]
+    Fall,
+    /// Cells fall, then columns slide left to close every empty column.
+    Collapse,
+}
+
+/// How far each cell moved by gravity went, by id. Cells that did not move are left out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FallDistance {
+    /// Rows fallen.
+    pub down: BTreeMap<usize, usize>,
+    /// Columns slid once the falling was over.
+    pub left: BTreeMap<usize, usize>,
+}
+
+/// Cell storage addressed by column and row. The game rules are provided on top of it, so every
+/// implementor shares the same `remove`, `apply_gravity` and `feed` semantics.
+pub trait Grid {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn column(&self, x: usize) -> &[Option<Cell>];
+    fn column_mut(&mut self, x: usize) -> &mut [Option<Cell>];
+    fn generate_cell(&mut self, cell_type: CellType) -> Cell;
+    fn generated_cells(&self) -> usize;
+    /// The cells a plain `Bomb` reaches.
+    fn blast_shape(&self) -> &BlastShape;
+    fn gravity(&self) -> Gravity;
+
+    fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        if x < self.width() {
+            self.column(x).get(y).cloned().flatten()
+        } else {
+            None
+        }
+    }
+
+    /// Whether the top row holds a cell, which means the next feed would push it off the board.
+    fn is_filled(&self) -> bool {
+        (0..self.width()).any(|x| self.get(x, 0).is_some())
+    }
+
+    /// How many cells each column holds, counting the gaps under its top cell.
+    fn column_heights(&self) -> Vec<usize> {
+        (0..self.width())
+            .map(|x| {
+                let column = self.column(x);
+                column.len() - column.iter().take_while(|cell| cell.is_none()).count()
+            })
+            .collect()
     }
 
-    pub fn remove(&mut self, x: usize, y: usize) -> Vec<(usize, usize, usize, usize, CellType)> {
+    /// A Zobrist hash of the layout. Cell ids are ignored, so equal layouts hash equally.
+    fn position_hash(&self) -> u64 {
+        let mut hash = 0;
+        for x in 0..self.width() {
+            for (y, cell) in self.column(x).iter().enumerate() {
+                if let Some(cell) = cell {
+                    hash ^= zobrist::key(x, y, cell.cell_type);
+                }
+            }
+        }
+        hash
+    }
+
+    /// Every distinct move, ordered by representative. Detonating any trigger of a move leaves the
+    /// same board, and empty cells are never triggers.
+    fn moves(&self) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::new();
+        let mut classes = BTreeMap::new();
+        for x in 0..self.width() {
+            for y in 0..self.height() {
+                if self.get(x, y).is_none() {
+                    continue;
+                }
+
+                let outcome = blast_closure(self, x, y);
+                let index = *classes.entry(outcome.clone()).or_insert_with(|| {
+                    let (removed, damaged) = outcome;
+                    moves.push(Move {
+                        x,
+                        y,
+                        triggers: Vec::new(),
+                        removed,
+                        damaged,
+                    });
+                    moves.len() - 1
+                });
+                moves[index].triggers.push((x, y));
+            }
+        }
+
+        moves
+    }
+
+    fn clear(&mut self) {
+        for x in 0..self.width() {
+            self.column_mut(x).fill(None);
+        }
+    }
+
+    /// Detonates the cell at `(x, y)`. Every cell is hit at most once per chain.
+    fn remove(&mut self, x: usize, y: usize) -> ChainReport {
+        let (width, height) = (self.width(), self.height());
+        let mut hit = vec![false; width * height];
         let mut queue = VecDeque::new();
         queue.push_back((x, y, 0));
-        let mut dists = Vec::new();
+        let mut report = ChainReport::default();
 
         while let Some((x, y, dist)) = queue.pop_front() {
-            let cell = self
-                .cells
-                .get_mut(x)
-                .and_then(|x| x.get_mut(y))
-                .and_then(|x| x.take());
-
-            if let Some(Cell { cell_type, id }) = cell {
-                dists.push((id, dist, x, y, cell_type));
-                if cell_type == CellType::Bomb {
-                    for (x, y) in adjacent_cells(x, y, WIDTH, HEIGHT) {
-                        queue.push_back((x, y, dist + 1));
-                    }
+            if x >= width || y >= height || hit[x * height + y] {
+                continue;
+            }
+            hit[x * height + y] = true;
+
+            let slot = &mut self.column_mut(x)[y];
+            if let Some(Cell { cell_type, id }) = *slot {
+                let cell = RemovedCell {
+                    id,
+                    distance: dist,
+                    x,
+                    y,
+                    cell_type,
+                };
+                if cell_type == CellType::Armored {
+                    *slot = Some(Cell::new(id, CellType::Tile));
+                    report.damaged.push(cell);
+                    continue;
+                }
+
+                *slot = None;
+                report.removed.push(cell);
+                for (x, y) in blast_area(cell_type, self.blast_shape(), x, y, width, height) {
+                    queue.push_back((x, y, dist + 1));
                 }
             }
         }
 
-        dists
+        report
     }
 
-    pub fn apply_gravity(&mut self) -> BTreeMap<usize, usize> {
-        let mut fall_distance = BTreeMap::new();
+    fn apply_gravity(&mut self) -> FallDistance {
+        let mut fall_distance = FallDistance::default();
 
-        for x in 0..WIDTH {
+        for x in 0..self.width() {
+            let column = self.column_mut(x);
             let mut blank_cells_below = 0;
 
-            for y in (0..HEIGHT).rev() {
-                if let Some(Cell { id, .. }) = self.cells[x][y] {
+            for y in (0..column.len()).rev() {
+                if let Some(Cell { id, .. }) = column[y] {
                     if blank_cells_below > 0 {
-                        fall_distance.insert(id, blank_cells_below);
+                        fall_distance.down.insert(id, blank_cells_below);
                     }
 
-                    self.cells[x].swap(y, y + blank_cells_below);
+                    column.swap(y, y + blank_cells_below);
                 } else {
                     blank_cells_below += 1;
                 }
             }
         }
 
+        if self.gravity() == Gravity::Collapse {
+            let mut empty_columns = 0;
+            for x in 0..self.width() {
+                if self.column(x).iter().all(Option::is_none) {
+                    empty_columns += 1;
+                    continue;
+                }
+                if empty_columns == 0 {
+                    continue;
+                }
+                for y in 0..self.height() {
+                    if let Some(cell) = self.column_mut(x)[y].take() {
+                        fall_distance.left.insert(cell.id, empty_columns);
+                        self.column_mut(x - empty_columns)[y] = Some(cell);
+                    }
+                }
+            }
+        }
+
         fall_distance
     }
 
-    pub fn feed(&mut self, row: &[CellType; WIDTH]) -> [Cell; WIDTH] {
-        let row = row.map(|cell| {
-            let cell = Cell::new(self.generated_cells, cell);
-            self.generated_cells += 1;
-            cell
-        });
+    fn feed(&mut self, row: &[CellType]) -> Vec<Cell> {
+        assert_eq!(row.len(), self.width());
+        let row: Vec<_> = row
+            .iter()
+            .map(|&cell_type| self.generate_cell(cell_type))
+            .collect();
 
-        for (cell, column) in row.iter().cloned().zip(self.cells.iter_mut()) {
+        for (x, &cell) in row.iter().enumerate() {
+            let column = self.column_mut(x);
             column.rotate_left(1);
             *column.last_mut().unwrap() = Some(cell);
         }
 
         row
     }
+
+    /// Renders the layout as rows of `.` for empty cells and [`CellType::to_char`] for the rest
+    /// (`#`, `*`, `@`, `-`, `|` and `+`), top row first. Cell ids are not kept.
+    fn to_notation(&self) -> String {
+        (0..self.height())
+            .map(|y| {
+                (0..self.width())
+                    .map(|x| self.get(x, y).map_or('.', |cell| cell.cell_type.to_char()))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(into = "DynamicBoard", try_from = "DynamicBoard")]
+pub struct Board<const WIDTH: usize, const HEIGHT: usize> {
+    pub cells: [[Option<Cell>; HEIGHT]; WIDTH],
+    pub blast_shape: BlastShape,
+    pub gravity: Gravity,
+    generated_cells: usize,
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Default for Board<WIDTH, HEIGHT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Board<WIDTH, HEIGHT> {
+    pub fn new() -> Self {
+        Board {
+            cells: [[None; HEIGHT]; WIDTH],
+            blast_shape: BlastShape::default(),
+            gravity: Gravity::default(),
+            generated_cells: 0,
+        }
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> Grid for Board<WIDTH, HEIGHT> {
+    fn width(&self) -> usize {
+        WIDTH
+    }
+
+    fn height(&self) -> usize {
+        HEIGHT
+    }
+
+    fn column(&self, x: usize) -> &[Option<Cell>] {
+        &self.cells[x]
+    }
+
+    fn column_mut(&mut self, x: usize) -> &mut [Option<Cell>] {
+        &mut self.cells[x]
+    }
+
+    fn generate_cell(&mut self, cell_type: CellType) -> Cell {
+        let cell = Cell::new(self.generated_cells, cell_type);
+        self.generated_cells += 1;
+        cell
+    }
+
+    fn generated_cells(&self) -> usize {
+        self.generated_cells
+    }
+
+    fn blast_shape(&self) -> &BlastShape {
+        &self.blast_shape
+    }
+
+    fn gravity(&self) -> Gravity {
+        self.gravity
+    }
+}
+
+/// A board whose dimensions are chosen at runtime.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawBoard")]
+pub struct DynamicBoard {
+    cells: Vec<Vec<Option<Cell>>>,
+    pub blast_shape: BlastShape,
+    pub gravity: Gravity,
+    height: usize,
+    generated_cells: usize,
+}
+
+impl DynamicBoard {
+    /// An empty board. Panics if either dimension is zero.
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "{}", BoardError::Empty);
+        DynamicBoard {
+            cells: vec![vec![None; height]; width],
+            blast_shape: BlastShape::default(),
+            gravity: Gravity::default(),
+            height,
+            generated_cells: 0,
+        }
+    }
+
+    /// The cells column by column, left to right, each column from the top down.
+    pub fn cells(&self) -> &[Vec<Option<Cell>>] {
+        &self.cells
+    }
+}
+
+impl Grid for DynamicBoard {
+    fn width(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn column(&self, x: usize) -> &[Option<Cell>] {
+        &self.cells[x]
+    }
+
+    fn column_mut(&mut self, x: usize) -> &mut [Option<Cell>] {
+        &mut self.cells[x]
+    }
+
+    fn generate_cell(&mut self, cell_type: CellType) -> Cell {
+        let cell = Cell::new(self.generated_cells, cell_type);
+        self.generated_cells += 1;
+        cell
+    }
+
+    fn generated_cells(&self) -> usize {
+        self.generated_cells
+    }
+
+    fn blast_shape(&self) -> &BlastShape {
+        &self.blast_shape
+    }
+
+    fn gravity(&self) -> Gravity {
+        self.gravity
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> From<Board<WIDTH, HEIGHT>> for DynamicBoard {
+    fn from(board: Board<WIDTH, HEIGHT>) -> Self {
+        DynamicBoard {
+            cells: board.cells.iter().map(|column| column.to_vec()).collect(),
+            blast_shape: board.blast_shape,
+            gravity: board.gravity,
+            height: HEIGHT,
+            generated_cells: board.generated_cells,
+        }
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> TryFrom<DynamicBoard> for Board<WIDTH, HEIGHT> {
+    type Error = BoardError;
+
+    fn try_from(board: DynamicBoard) -> Result<Self, BoardError> {
+        let found = (board.width(), board.height());
+        if found != (WIDTH, HEIGHT) {
+            return Err(BoardError::DimensionMismatch {
+                expected: (WIDTH, HEIGHT),
+                found,
+            });
+        }
+
+        let mut cells = [[None; HEIGHT]; WIDTH];
+        for (to, from) in cells.iter_mut().zip(board.cells) {
+            to.copy_from_slice(&from);
+        }
+        Ok(Board {
+            cells,
+            blast_shape: board.blast_shape,
+            gravity: board.gravity,
+            generated_cells: board.generated_cells,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RawBoard {
+    cells: Vec<Vec<Option<Cell>>>,
+    #[serde(default)]
+    blast_shape: BlastShape,
+    #[serde(default)]
+    gravity: Gravity,
+    height: usize,
+    generated_cells: usize,
+}
+
+impl TryFrom<RawBoard> for DynamicBoard {
+    type Error = BoardError;
+
+    fn try_from(raw: RawBoard) -> Result<Self, BoardError> {
+        if raw.cells.is_empty() || raw.height == 0 {
+            return Err(BoardError::Empty);
+        }
+        if raw.cells.iter().any(|column| column.len() != raw.height) {
+            return Err(BoardError::RaggedRows);
+        }
+        Ok(DynamicBoard {
+            cells: raw.cells,
+            blast_shape: raw.blast_shape,
+            gravity: raw.gravity,
+            height: raw.height,
+            generated_cells: raw.generated_cells,
+        })
+    }
+}
+
+impl fmt::Display for DynamicBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_notation())
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> fmt::Display for Board<WIDTH, HEIGHT> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_notation())
+    }
+}
+
+/// Parses the notation of [`Grid::to_notation`]. Ids are assigned row by row from the bottom, left
+/// to right, as if the rows had been fed in order.
+impl FromStr for DynamicBoard {
+    type Err = BoardError;
+
+    fn from_str(s: &str) -> Result<Self, BoardError> {
+        let rows = s
+            .lines()
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .map(|row| row.chars().map(parse_cell).collect())
+            .collect::<Result<Vec<Vec<_>>, _>>()?;
+
+        let Some(width) = rows.first().map(|row| row.len()) else {
+            return Err(BoardError::Empty);
+        };
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(BoardError::RaggedRows);
+        }
+
+        let mut board = DynamicBoard::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate().rev() {
+            for (x, &cell_type) in row.iter().enumerate() {
+                board.cells[x][y] = cell_type.map(|cell_type| board.generate_cell(cell_type));
+            }
+        }
+        Ok(board)
+    }
+}
+
+impl<const WIDTH: usize, const HEIGHT: usize> FromStr for Board<WIDTH, HEIGHT> {
+    type Err = BoardError;
+
+    fn from_str(s: &str) -> Result<Self, BoardError> {
+        s.parse::<DynamicBoard>()?.try_into()
+    }
+}
+
+fn parse_cell(c: char) -> Result<Option<CellType>, BoardError> {
+    match c {
+        '.' => Ok(None),
+        c => CellType::from_char(c)
+            .map(Some)
+            .ok_or(BoardError::UnknownCell(c)),
+    }
 }
 
 #[cfg(test)]
@@ -130,6 +660,8 @@ mod test {
     ) -> Board<WIDTH, HEIGHT> {
         Board {
             cells,
+            blast_shape: BlastShape::default(),
+            gravity: Gravity::default(),
             generated_cells: 0,
         }
     }
@@ -138,6 +670,14 @@ mod test {
         Some(Cell { id, cell_type })
     }
 
+    fn tuples(report: ChainReport) -> Vec<(usize, usize, usize, usize, CellType)> {
+        report
+            .removed
+            .into_iter()
+            .map(|cell| (cell.id, cell.distance, cell.x, cell.y, cell.cell_type))
+            .collect()
+    }
+
     trait Sorted {
         fn sorted(&self) -> Self;
     }
@@ -152,15 +692,18 @@ mod test {
 
     #[test]
     fn test_remove() {
-        let mut board = from_cells::<3, 4>([
-            [None, cell(6, Tile), cell(3, Tile), cell(0, Tile)],
-            [None, cell(7, Tile), cell(4, Bomb), cell(1, Tile)],
-            [None, cell(8, Tile), cell(5, Tile), cell(2, Tile)],
-        ]);
-
-        assert_eq!(board.remove(0, 3), vec![(0, 0, 0, 3, Tile)]);
+        let mut board: Board<3, 4> = "
+            ...
+            ###
+            #*#
+            ###
+        "
+        .parse()
+        .unwrap();
+
+        assert_eq!(tuples(board.remove(0, 3)), vec![(0, 0, 0, 3, Tile)]);
         assert_eq!(
-            board.remove(1, 2).sorted(),
+            tuples(board.remove(1, 2)).sorted(),
             vec![
                 (1, 1, 1, 3, Tile),
                 (2, 1, 2, 3, Tile),
@@ -175,6 +718,38 @@ mod test {
         assert_eq!(board.cells, [[None; 4]; 3]);
     }
 
+    #[test]
+    fn test_special_cells() {
+        let mut board: DynamicBoard = "
+            #@#..
+            #-|+*
+            ..#.#
+            .#@##
+        "
+        .parse()
+        .unwrap();
+
+        let report = board.remove(1, 1);
+        assert_eq!(report.removed_count(), 9);
+        assert_eq!(report.bombs(), 4);
+        assert_eq!(report.depth(), 2);
+        assert_eq!(
+            report
+                .damaged
+                .iter()
+                .map(|cell| (cell.x, cell.y, cell.distance))
+                .collect::<Vec<_>>(),
+            vec![(2, 3, 2)]
+        );
+        assert_eq!(board.to_string(), "#@...\n.....\n.....\n.##.#");
+
+        let report = board.remove(1, 0);
+        assert!(report.removed.is_empty());
+        assert_eq!(report.damaged.len(), 1);
+        assert!(!report.is_empty());
+        assert_eq!(board.remove(1, 0).removed_count(), 1);
+    }
+
     #[test]
     fn test_apply_gravity() {
         let mut board = from_cells::<3, 4>([
@@ -188,7 +763,7 @@ mod test {
         map.insert(1, 1);
         map.insert(2, 1);
         map.insert(4, 1);
-        assert_eq!(board.apply_gravity(), map);
+        assert_eq!(board.apply_gravity().down, map);
 
         assert_eq!(
             board.cells,
@@ -200,6 +775,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_collapse() {
+        let mut board: DynamicBoard = "
+            *...
+            #.#.
+            #..*
+        "
+        .parse()
+        .unwrap();
+        board.gravity = Gravity::Collapse;
+        assert_eq!(board.column_heights(), [3, 0, 2, 1]);
+        board.remove(0, 2);
+        board.remove(0, 1);
+        let fall_distance = board.apply_gravity();
+        assert_eq!(board.to_notation(), "....\n....\n*#*.");
+        assert_eq!(board.column_heights(), [1, 1, 1, 0]);
+        assert_eq!(fall_distance.down.len(), 2);
+        assert_eq!(fall_distance.left.values().collect::<Vec<_>>(), [&1, &1]);
+
+        let mut fall = board.clone();
+        fall.gravity = Gravity::Fall;
+        assert!(fall.apply_gravity().left.is_empty());
+    }
+
     #[test]
     fn test_feed() {
         let mut board = from_cells::<4, 3>([
@@ -232,29 +831,118 @@ mod test {
     }
 
     #[test]
-    fn test_adjacent_cells() {
+    fn test_dynamic_board() {
+        let mut board = Board::<3, 4>::new();
+        let mut dynamic = DynamicBoard::new(3, 4);
+        assert_eq!((dynamic.width(), dynamic.height()), (3, 4));
+
+        for row in [[Tile, Bomb, Tile], [Bomb, Tile, Tile], [Tile, Tile, Tile]] {
+            assert_eq!(board.feed(&row), dynamic.feed(&row));
+        }
+        assert_eq!(board.remove(0, 2), dynamic.remove(0, 2));
+        assert_eq!(board.apply_gravity(), dynamic.apply_gravity());
+        assert_eq!(DynamicBoard::from(board).cells, dynamic.cells);
+    }
+
+    #[test]
+    fn test_notation() {
+        let notation = "....\n.*#.\n#*##";
+        let board: DynamicBoard = notation.parse().unwrap();
+        assert_eq!(board.to_string(), notation);
+        assert_eq!(board.generated_cells(), 6);
+        assert_eq!(board.get(0, 2), cell(0, Tile));
+        assert_eq!(board.get(2, 1), cell(5, Tile));
+
         assert_eq!(
-            adjacent_cells(1, 1, 3, 3).collect::<Vec<_>>(),
-            vec![
-                (0, 0),
-                (0, 1),
-                (0, 2),
-                (1, 0),
-                (1, 2),
-                (2, 0),
-                (2, 1),
-                (2, 2)
-            ]
+            "#.\n##.".parse::<DynamicBoard>().err(),
+            Some(BoardError::RaggedRows)
         );
-
         assert_eq!(
-            adjacent_cells(0, 0, 3, 3).collect::<Vec<_>>(),
-            vec![(0, 1), (1, 0), (1, 1)]
+            "#?".parse::<DynamicBoard>().err(),
+            Some(BoardError::UnknownCell('?'))
+        );
+        assert_eq!("\n".parse::<DynamicBoard>().err(), Some(BoardError::Empty));
+        assert_eq!(
+            "##".parse::<Board<3, 1>>().err(),
+            Some(BoardError::DimensionMismatch {
+                expected: (3, 1),
+                found: (2, 1)
+            })
         );
+    }
+
+    #[test]
+    fn test_serde() {
+        let mut board: Board<3, 3> = "...\n*#*\n#*#".parse().unwrap();
+        board.feed(&[Tile, Tile, Bomb]);
+
+        let json = serde_json::to_string(&board).unwrap();
+        let restored: Board<3, 3> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.cells, board.cells);
+        assert_eq!(restored.generated_cells(), 9);
+
+        let dynamic: DynamicBoard = serde_json::from_str(&json).unwrap();
+        assert_eq!(dynamic.to_string(), board.to_string());
+        assert!(serde_json::from_str::<Board<4, 3>>(&json).is_err());
+
+        let empty = r#"{ "cells": [[]], "height": 0, "generated_cells": 0 }"#;
+        assert!(serde_json::from_str::<DynamicBoard>(empty).is_err());
+    }
 
+    #[test]
+    fn test_moves() {
+        let board: DynamicBoard = "
+            *#.
+            .*#
+            @#*
+        "
+        .parse()
+        .unwrap();
+
+        let moves = board.moves();
         assert_eq!(
-            adjacent_cells(2, 2, 3, 3).collect::<Vec<_>>(),
-            vec![(1, 1), (1, 2), (2, 1)]
+            moves.iter().map(|x| (x.x, x.y)).collect::<Vec<_>>(),
+            vec![(0, 0), (0, 2), (1, 0), (1, 2), (2, 1)]
         );
+        assert_eq!(moves[0].triggers, vec![(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(moves[0].removed.len(), 6);
+        assert_eq!(moves[0].damaged, vec![(0, 2)]);
+        assert!(moves[1].removed.is_empty());
+
+        for m in moves {
+            for &(x, y) in &m.triggers {
+                let mut board = board.clone();
+                let report = board.remove(x, y);
+                let positions = |cells: &[RemovedCell]| {
+                    cells
+                        .iter()
+                        .map(|cell| (cell.x, cell.y))
+                        .collect::<Vec<_>>()
+                        .sorted()
+                };
+                assert_eq!(positions(&report.removed), m.removed);
+                assert_eq!(positions(&report.damaged), m.damaged);
+            }
+        }
+    }
+
+    #[test]
+    fn test_blast_shape() {
+        let notation = "
+            *.#
+            #*.
+            ..*
+        ";
+        let mut board: DynamicBoard = notation.parse().unwrap();
+        assert_eq!(board.remove(1, 1).removed_count(), 5);
+
+        let mut board: DynamicBoard = notation.parse().unwrap();
+        board.blast_shape = BlastShape::Orthogonal;
+        assert_eq!(board.remove(1, 1).removed_count(), 2);
+        assert_eq!(board.to_string(), "*.#\n...\n..*");
+
+        let json = serde_json::to_string(&board).unwrap();
+        let restored: Board<3, 3> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.blast_shape, BlastShape::Orthogonal);
     }
 }
@@ -0,0 +1,136 @@
+use crate::board::CellType;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RemovedCell {
+    pub id: usize,
+    pub distance: usize,
+    pub x: usize,
+    pub y: usize,
+    pub cell_type: CellType,
+}
+
+/// What a single `remove` did to the board. Cells are listed in detonation order, so their
+/// distances never decrease.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChainReport {
+    pub removed: Vec<RemovedCell>,
+    /// Armored cells that were hit. They stay on the board as plain tiles.
+    pub damaged: Vec<RemovedCell>,
+}
+
+impl ChainReport {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.damaged.is_empty()
+    }
+
+    /// Cells the chain removed, not counting armored cells it only damaged.
+    pub fn removed_count(&self) -> usize {
+        self.removed.len()
+    }
+
+    pub fn bombs(&self) -> usize {
+        self.removed
+            .iter()
+            .filter(|cell| cell.cell_type.is_bomb())
+            .count()
+    }
+
+    /// The clicked cell, if it held anything. An armored trigger is only damaged, and stops the
+    /// chain before anything is removed.
+    pub fn trigger(&self) -> Option<&RemovedCell> {
+        self.removed.first().or_else(|| self.damaged.first())
+    }
+
+    /// The largest distance the chain travelled. A lone cell has a depth of zero.
+    pub fn depth(&self) -> usize {
+        self.removed.last().map_or(0, |cell| cell.distance)
+    }
+
+    /// Removed cells grouped by distance, starting with the trigger.
+    pub fn waves(&self) -> Vec<&[RemovedCell]> {
+        let mut waves = Vec::new();
+        let mut rest = self.removed.as_slice();
+        while let Some(first) = rest.first() {
+            let len = rest
+                .iter()
+                .take_while(|cell| cell.distance == first.distance)
+                .count();
+            let (wave, tail) = rest.split_at(len);
+            waves.push(wave);
+            rest = tail;
+        }
+        waves
+    }
+
+    pub fn bombs_per_wave(&self) -> Vec<usize> {
+        self.waves()
+            .iter()
+            .map(|wave| wave.iter().filter(|cell| cell.cell_type.is_bomb()).count())
+            .collect()
+    }
+
+    /// `(left, top, right, bottom)` of the removed cells, inclusive.
+    pub fn bounding_box(&self) -> Option<(usize, usize, usize, usize)> {
+        let first = self.removed.first()?;
+        Some(self.removed.iter().fold(
+            (first.x, first.y, first.x, first.y),
+            |(left, top, right, bottom), cell| {
+                (
+                    left.min(cell.x),
+                    top.min(cell.y),
+                    right.max(cell.x),
+                    bottom.max(cell.y),
+                )
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::board::{CellType, DynamicBoard, Grid};
+
+    #[test]
+    fn test_chain_report() {
+        let mut board: DynamicBoard = "
+            #....
+            .*...
+            ..*..
+            ..#*#
+        "
+        .parse()
+        .unwrap();
+
+        let report = board.remove(1, 1);
+        assert_eq!(report.removed_count(), 6);
+        assert_eq!(report.bombs(), 3);
+        assert_eq!(report.depth(), 3);
+        assert_eq!(
+            report.trigger().map(|cell| (cell.x, cell.y, cell.distance)),
+            Some((1, 1, 0))
+        );
+        assert_eq!(
+            report.waves().iter().map(|x| x.len()).collect::<Vec<_>>(),
+            vec![1, 2, 2, 1]
+        );
+        assert_eq!(report.bombs_per_wave(), vec![1, 1, 1, 0]);
+        assert_eq!(report.bounding_box(), Some((0, 0, 4, 3)));
+
+        let report = board.remove(0, 0);
+        assert!(report.is_empty());
+        assert_eq!(report.depth(), 0);
+        assert!(report.waves().is_empty());
+        assert_eq!(report.bounding_box(), None);
+
+        let mut board: DynamicBoard = "@*".parse().unwrap();
+        let report = board.remove(0, 0);
+        assert_eq!(report.removed_count(), 0);
+        assert_eq!(
+            report
+                .trigger()
+                .map(|cell| (cell.x, cell.y, cell.cell_type)),
+            Some((0, 0, CellType::Armored))
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! The rules of normal and master mode without any rendering. A `GameState` takes the actions a
+//! player makes and reports what they did as events, so a view only has to animate them, and
+//! replays are checked by the same code the game was played with.
+
+use crate::board::{CellType, DynamicBoard, Grid};
+use crate::chain::ChainReport;
+use crate::generator::{BagGenerator, RowGenerator, SpreadGenerator};
+use crate::grade::{score_rank, GradeManager, SINGLE_FREQUENCY};
+use crate::replay::{Action, Mode, Outcome, Replay, Rules};
+use crate::scoring::ScoringRule;
+
+/// What an action did, in the order it happened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The chain set off at `(x, y)`. The board has fallen since.
+    Removed {
+        x: usize,
+        y: usize,
+        report: ChainReport,
+    },
+    Fed(Vec<CellType>),
+    /// Master mode reached a new grade, named here.
+    Promoted(&'static str),
+    /// Master mode entered `section`. Reaching the last section as a master clears the board,
+    /// which stays invisible from then on.
+    SectionChanged {
+        section: usize,
+        invisible: bool,
+    },
+    GameOver,
+}
+
+pub struct GameState {
+    mode: Mode,
+    rules: Rules,
+    board: DynamicBoard,
+    generator: Box<dyn RowGenerator>,
+    scoring: Box<dyn ScoringRule>,
+    grade: GradeManager,
+    replay: Replay,
+    frame: usize,
+    score: usize,
+    level: usize,
+    section: usize,
+    until_single: usize,
+    single_frequency: usize,
+    invisible: bool,
+}
+
+impl GameState {
+    pub fn new(mode: Mode, seed: u64, rules: Rules) -> Self {
+        let mut board = DynamicBoard::new(rules.width, rules.height);
+        board.blast_shape = rules.blast_shape.clone();
+        board.gravity = rules.gravity;
+        let generator: Box<dyn RowGenerator> = match mode {
+            Mode::Normal => Box::new(BagGenerator::new(rules.width, seed)),
+            Mode::Master => Box::new(SpreadGenerator::new(rules.width, seed)),
+        };
+        GameState {
+            mode,
+            board,
+            generator,
+            scoring: rules.scoring_rule(mode),
+            grade: GradeManager::new(),
+            replay: Replay::new(mode, seed, rules.clone()),
+            rules,
+            frame: 0,
+            score: 0,
+            level: 0,
+            section: 0,
+            until_single: 999,
+            single_frequency: 999,
+            invisible: false,
+        }
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn board(&self) -> &DynamicBoard {
+        &self.board
+    }
+
+    /// The actions that changed the game, with the frames they happened on.
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Points scored in normal mode. Master mode keeps its score in the grade instead.
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// Bombs removed, which is the level in master mode.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// The bombs limit in normal mode, or the level limit in master mode.
+    pub fn limit(&self) -> usize {
+        match self.mode {
+            Mode::Normal => self.rules.bombs_limit,
+            Mode::Master => self.rules.level_limit,
+        }
+    }
+
+    pub fn section(&self) -> usize {
+        self.section
+    }
+
+    /// Rows left before the next single-bomb row in master mode.
+    pub fn until_single(&self) -> usize {
+        self.until_single
+    }
+
+    pub fn single_frequency(&self) -> usize {
+        self.single_frequency
+    }
+
+    pub fn is_invisible(&self) -> bool {
+        self.invisible
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.board.is_filled() || self.level >= self.limit()
+    }
+
+    /// The score rank in normal mode, or the grade in master mode.
+    pub fn grade(&self) -> &'static str {
+        match self.mode {
+            Mode::Normal => score_rank(self.score),
+            Mode::Master => {
+                let grade = self.grade.current_grade().grade;
+                if grade == "master" && self.level >= self.limit() && !self.board.is_filled() {
+                    "Grandmaster"
+                } else {
+                    grade
+                }
+            }
+        }
+    }
+
+    /// The grade score and the score the next grade needs.
+    pub fn grade_condition(&self) -> (isize, isize) {
+        (
+            self.grade.score(),
+            self.grade.current_grade().required_score,
+        )
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        Outcome {
+            score: self.score,
+            level: self.level,
+            grade: self.grade().to_string(),
+        }
+    }
+
+    /// Moves the clock to `frame`, letting the master mode grade decay. Earlier frames are
+    /// ignored.
+    pub fn advance(&mut self, frame: usize) {
+        if frame > self.frame {
+            self.grade.decay(frame - self.frame);
+            self.frame = frame;
+        }
+    }
+
+    /// Acts on `action` at `frame`. Actions that change nothing, such as removing an empty cell
+    /// or anything after the game is over, report no events and are not recorded.
+    pub fn apply(&mut self, frame: usize, action: Action) -> Vec<Event> {
+        self.advance(frame);
+        if self.is_over() {
+            return Vec::new();
+        }
+
+        let mut events = match action {
+            Action::Feed => vec![self.feed()],
+            Action::Remove { x, y } => {
+                if x >= self.board.width() || y >= self.board.height() {
+                    return Vec::new();
+                }
+                let report = self.board.remove(x, y);
+                if report.is_empty() {
+                    return Vec::new();
+                }
+                self.remove(x, y, report)
+            }
+        };
+        self.replay.record(self.frame, action);
+
+        if self.is_over() {
+            events.push(Event::GameOver);
+        }
+        events
+    }
+
+    fn remove(&mut self, x: usize, y: usize, report: ChainReport) -> Vec<Event> {
+        self.level += report.bombs();
+        let mut is_promoted = false;
+        let mut section_changed = None;
+        match self.mode {
+            Mode::Normal => self.score += self.scoring.score(&report, 0),
+            Mode::Master => {
+                let section = (self.level / 100).min(9);
+                is_promoted = self.grade.add(section, &report, self.scoring.as_mut());
+                if self.section < section {
+                    self.section = section;
+                    self.single_frequency = SINGLE_FREQUENCY[section];
+                    self.until_single = self.single_frequency;
+                    if section == 9 && self.grade() == "master" {
+                        self.level = 900;
+                        self.board.clear();
+                        self.generator.reset();
+                        self.invisible = true;
+                    }
+                    section_changed = Some(Event::SectionChanged {
+                        section,
+                        invisible: self.invisible,
+                    });
+                }
+            }
+        }
+
+        let mut events = vec![Event::Removed { x, y, report }];
+        events.extend(section_changed);
+        self.board.apply_gravity();
+        events.push(self.feed());
+        if is_promoted || self.grade() == "Grandmaster" {
+            events.push(Event::Promoted(self.grade()));
+        }
+        events
+    }
+
+    fn feed(&mut self) -> Event {
+        let bombs = match self.mode {
+            Mode::Normal => 2,
+            Mode::Master if self.until_single == 0 => {
+                self.until_single = self.single_frequency - 1;
+                1
+            }
+            Mode::Master => {
+                self.until_single -= 1;
+                2
+            }
+        };
+        let row = self.generator.next_row(bombs);
+        self.board.feed(&row);
+        Event::Fed(row)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn small_rules() -> Rules {
+        Rules {
+            width: 2,
+            height: 3,
+            ..Rules::default()
+        }
+    }
+
+    fn kinds(events: &[Event]) -> Vec<&'static str> {
+        events
+            .iter()
+            .map(|event| match event {
+                Event::Removed { .. } => "Removed",
+                Event::Fed(_) => "Fed",
+                Event::Promoted(_) => "Promoted",
+                Event::SectionChanged { .. } => "SectionChanged",
+                Event::GameOver => "GameOver",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut state = GameState::new(Mode::Normal, 7, small_rules());
+        assert_eq!(kinds(&state.apply(0, Action::Feed)), ["Fed"]);
+        assert!(state.apply(10, Action::Remove { x: 0, y: 0 }).is_empty());
+        assert!(state.apply(10, Action::Remove { x: 5, y: 0 }).is_empty());
+
+        let events = state.apply(30, Action::Remove { x: 0, y: 2 });
+        assert_eq!(kinds(&events), ["Removed", "Fed"]);
+        assert!(state.score() > 0);
+        assert_eq!(state.replay().actions.len(), 2);
+        assert_eq!(state.replay().simulate().unwrap(), state.outcome());
+
+        let mut state = GameState::new(Mode::Normal, 7, small_rules());
+        let mut events = Vec::new();
+        while !state.is_over() {
+            events = state.apply(0, Action::Feed);
+        }
+        assert_eq!(kinds(&events), ["Fed", "GameOver"]);
+        assert!(state.apply(0, Action::Feed).is_empty());
+    }
+
+    #[test]
+    fn test_master() {
+        let rules = Rules {
+            level_limit: 6,
+            ..small_rules()
+        };
+        let mut state = GameState::new(Mode::Master, 7, rules);
+        state.apply(60, Action::Feed);
+        let mut events = Vec::new();
+        for frame in [100, 200, 300] {
+            events.extend(state.apply(frame, Action::Remove { x: 1, y: 2 }));
+        }
+        assert_eq!(state.level(), 6);
+        assert!(state.is_over());
+        assert_eq!(events.last(), Some(&Event::GameOver));
+        assert_eq!(state.replay().simulate().unwrap(), state.outcome());
+
+        state.advance(100_000);
+        let (score, _) = state.grade_condition();
+        assert_eq!(score, 0);
+    }
+}
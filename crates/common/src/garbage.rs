@@ -0,0 +1,205 @@
+//! Garbage rows sent between players in online versus. A chain attacks the opponent with rows of
+//! tiles, and long chains with armored rows. Before an attack is sent it cancels the garbage
+//! waiting for its own player. With more than one opponent, a targeting strategy picks who it goes
+//! to.
+
+use crate::board::CellType;
+use crate::chain::ChainReport;
+use crate::rng::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Garbage fed to a player in one turn at most. The rest waits for the next turn.
+pub const MAX_ROWS_PER_TURN: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attack {
+    /// Rows of plain tiles.
+    pub rows: usize,
+    /// Rows of armored tiles with a single plain tile in them.
+    pub armored: usize,
+}
+
+impl Attack {
+    /// The first bomb of a chain sends nothing and every other bomb sends a row. Every two waves
+    /// after the first add an armored row.
+    pub fn from_report(report: &ChainReport) -> Self {
+        Attack {
+            rows: report.bombs().saturating_sub(1),
+            armored: report.depth().saturating_sub(1) / 2,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.rows + self.armored
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// The rows to feed, armored rows last so they end up at the bottom.
+    pub fn to_rows(self, width: usize, rng: &mut Rng) -> Vec<Vec<CellType>> {
+        let mut rows = vec![vec![CellType::Tile; width]; self.rows];
+        for _ in 0..self.armored {
+            let mut row = vec![CellType::Armored; width];
+            row[rng.below(width as u64) as usize] = CellType::Tile;
+            rows.push(row);
+        }
+        rows
+    }
+}
+
+/// The garbage waiting to be fed to a player.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GarbageQueue {
+    pending: Attack,
+}
+
+impl GarbageQueue {
+    pub fn new() -> Self {
+        GarbageQueue::default()
+    }
+
+    pub fn pending(&self) -> Attack {
+        self.pending
+    }
+
+    pub fn receive(&mut self, attack: Attack) {
+        self.pending.rows += attack.rows;
+        self.pending.armored += attack.armored;
+    }
+
+    /// Cancels pending garbage with `attack`, a row for a row and armored rows first. Returns the
+    /// part of the attack left to send, which keeps its armored rows first.
+    pub fn offset(&mut self, attack: Attack) -> Attack {
+        let mut power = attack.total();
+        for pending in [&mut self.pending.armored, &mut self.pending.rows] {
+            let cancelled = power.min(*pending);
+            *pending -= cancelled;
+            power -= cancelled;
+        }
+        let armored = attack.armored.min(power);
+        Attack {
+            rows: power - armored,
+            armored,
+        }
+    }
+
+    /// Takes up to `max` rows to feed now, plain rows first.
+    pub fn take(&mut self, max: usize) -> Attack {
+        let rows = self.pending.rows.min(max);
+        let armored = self.pending.armored.min(max - rows);
+        self.pending.rows -= rows;
+        self.pending.armored -= armored;
+        Attack { rows, armored }
+    }
+}
+
+/// Who an attack goes to when there is more than one opponent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Targeting {
+    #[default]
+    Random,
+    /// The opponents whose attacks target the player, or anyone if nobody does.
+    Attackers,
+    /// The opponent with the lowest stack.
+    Lowest,
+}
+
+/// What targeting knows about an opponent still in the match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Opponent {
+    /// The height of the opponent's highest column.
+    pub stack: usize,
+    /// Whether the opponent's attacks target the player.
+    pub attacking: bool,
+}
+
+impl Targeting {
+    /// The index of the opponent to attack, or `None` if no one is left. Ties are broken at
+    /// random.
+    pub fn choose(self, opponents: &[Opponent], rng: &mut Rng) -> Option<usize> {
+        let candidates: Vec<usize> = match self {
+            Targeting::Random => (0..opponents.len()).collect(),
+            Targeting::Attackers => {
+                let attackers: Vec<usize> = (0..opponents.len())
+                    .filter(|&i| opponents[i].attacking)
+                    .collect();
+                if attackers.is_empty() {
+                    return Targeting::Random.choose(opponents, rng);
+                }
+                attackers
+            }
+            Targeting::Lowest => {
+                let lowest = opponents.iter().map(|opponent| opponent.stack).min()?;
+                (0..opponents.len())
+                    .filter(|&i| opponents[i].stack == lowest)
+                    .collect()
+            }
+        };
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(candidates[rng.below(candidates.len() as u64) as usize])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{DynamicBoard, Grid};
+
+    fn attack(rows: usize, armored: usize) -> Attack {
+        Attack { rows, armored }
+    }
+
+    #[test]
+    fn test_attack() {
+        let mut board: DynamicBoard = "#*.\n**#".parse().unwrap();
+        let report = board.remove(0, 1);
+        assert_eq!(Attack::from_report(&report), attack(2, 0));
+
+        let rows = attack(1, 2).to_rows(4, &mut Rng::new(0));
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0], [CellType::Tile; 4]);
+        for row in &rows[1..] {
+            let tiles = row.iter().filter(|&&cell| cell == CellType::Tile).count();
+            assert_eq!(tiles, 1);
+        }
+    }
+
+    #[test]
+    fn test_queue() {
+        let mut queue = GarbageQueue::new();
+        queue.receive(attack(3, 1));
+        assert_eq!(queue.offset(attack(2, 0)), Attack::default());
+        assert_eq!(queue.pending(), attack(2, 0));
+
+        let sent = queue.offset(attack(2, 2));
+        assert_eq!(sent, attack(0, 2));
+        assert!(queue.pending().is_empty());
+
+        queue.receive(attack(3, 3));
+        assert_eq!(queue.take(4), attack(3, 1));
+        assert_eq!(queue.take(4), attack(0, 2));
+        assert!(queue.take(4).is_empty());
+    }
+
+    #[test]
+    fn test_targeting() {
+        let mut rng = Rng::new(0);
+        let opponent = |stack, attacking| Opponent { stack, attacking };
+        assert_eq!(Targeting::Random.choose(&[], &mut rng), None);
+
+        let opponents = [opponent(5, false), opponent(2, true), opponent(7, true)];
+        assert_eq!(Targeting::Lowest.choose(&opponents, &mut rng), Some(1));
+        for _ in 0..20 {
+            let target = Targeting::Attackers.choose(&opponents, &mut rng).unwrap();
+            assert_ne!(target, 0);
+            assert!(Targeting::Random.choose(&opponents, &mut rng).unwrap() < 3);
+        }
+
+        let peaceful = [opponent(5, false), opponent(2, false)];
+        assert!(Targeting::Attackers.choose(&peaceful, &mut rng).is_some());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::board::CellType;
+use crate::rng::Rng;
+use std::collections::BTreeMap;
+
+/// Produces the rows fed into a board. Implementations are seeded, so the same seed gives the same
+/// rows everywhere.
+pub trait RowGenerator {
+    /// Generates the next row, placing `bombs` bombs among tiles.
+    fn next_row(&mut self, bombs: usize) -> Vec<CellType>;
+
+    /// Every row `next_row(bombs)` could return next, with its probability.
+    fn outcomes(&self, bombs: usize) -> Vec<(f64, Vec<CellType>)>;
+
+    /// Forgets which rows have been generated so far.
+    fn reset(&mut self) {}
+}
+
+fn combinations(first: usize, width: usize, count: usize) -> Vec<Vec<usize>> {
+    if count == 0 {
+        return vec![Vec::new()];
+    }
+
+    (first..width)
+        .flat_map(|x| {
+            combinations(x + 1, width, count - 1)
+                .into_iter()
+                .map(move |rest| [vec![x], rest].concat())
+        })
+        .collect()
+}
+
+fn row_with_bombs(width: usize, bombs: &[usize]) -> Vec<CellType> {
+    let mut row = vec![CellType::Tile; width];
+    for &x in bombs {
+        row[x] = CellType::Bomb;
+    }
+    row
+}
+
+/// Draws bomb positions from a shuffled bag holding every combination of columns once.
+#[derive(Clone, Debug)]
+pub struct BagGenerator {
+    rng: Rng,
+    width: usize,
+    bombs: usize,
+    bag: Vec<Vec<usize>>,
+}
+
+impl BagGenerator {
+    pub fn new(width: usize, seed: u64) -> Self {
+        BagGenerator {
+            rng: Rng::new(seed),
+            width,
+            bombs: 0,
+            bag: Vec::new(),
+        }
+    }
+
+    fn refill(&mut self, bombs: usize) {
+        let mut bag = combinations(0, self.width, bombs.min(self.width));
+        self.rng.shuffle(&mut bag);
+        self.bombs = bombs;
+        self.bag = bag;
+    }
+}
+
+impl RowGenerator for BagGenerator {
+    fn next_row(&mut self, bombs: usize) -> Vec<CellType> {
+        if self.bag.is_empty() || self.bombs != bombs {
+            self.refill(bombs);
+        }
+        let bombs = self.bag.pop().unwrap();
+        row_with_bombs(self.width, &bombs)
+    }
+
+    fn outcomes(&self, bombs: usize) -> Vec<(f64, Vec<CellType>)> {
+        let bag = if self.bag.is_empty() || self.bombs != bombs {
+            combinations(0, self.width, bombs.min(self.width))
+        } else {
+            self.bag.clone()
+        };
+        let probability = 1. / bag.len() as f64;
+        bag.iter()
+            .map(|bombs| (probability, row_with_bombs(self.width, bombs)))
+            .collect()
+    }
+
+    fn reset(&mut self) {
+        self.bag.clear();
+    }
+}
+
+/// Weights each column by how rarely it has had a bomb, so bombs spread evenly across the board.
+#[derive(Clone, Debug)]
+pub struct SpreadGenerator {
+    rng: Rng,
+    generated: Vec<usize>,
+}
+
+impl SpreadGenerator {
+    pub fn new(width: usize, seed: u64) -> Self {
+        SpreadGenerator {
+            rng: Rng::new(seed),
+            generated: vec![0; width],
+        }
+    }
+
+    fn weights(&self) -> Vec<u64> {
+        let max = self.generated.iter().copied().max().unwrap_or(0);
+        self.generated
+            .iter()
+            .map(|x| 1 << ((max - x) * 2).min(48))
+            .collect()
+    }
+}
+
+impl RowGenerator for SpreadGenerator {
+    fn next_row(&mut self, bombs: usize) -> Vec<CellType> {
+        let mut weights = self.weights();
+        let mut picked = Vec::new();
+
+        for _ in 0..bombs.min(weights.len()) {
+            let mut r = self.rng.below(weights.iter().sum());
+            let bomb = weights
+                .iter()
+                .position(|&weight| {
+                    if r < weight {
+                        true
+                    } else {
+                        r -= weight;
+                        false
+                    }
+                })
+                .unwrap();
+            weights[bomb] = 0;
+            picked.push(bomb);
+        }
+
+        for &bomb in &picked {
+            self.generated[bomb] += 1;
+        }
+        row_with_bombs(self.generated.len(), &picked)
+    }
+
+    fn outcomes(&self, bombs: usize) -> Vec<(f64, Vec<CellType>)> {
+        fn visit(
+            weights: &mut [u64],
+            bombs: usize,
+            probability: f64,
+            picked: &mut Vec<usize>,
+            outcomes: &mut BTreeMap<Vec<usize>, f64>,
+        ) {
+            if picked.len() == bombs {
+                let mut key = picked.clone();
+                key.sort_unstable();
+                *outcomes.entry(key).or_default() += probability;
+                return;
+            }
+
+            let sum = weights.iter().sum::<u64>() as f64;
+            for x in 0..weights.len() {
+                let weight = weights[x];
+                if weight == 0 {
+                    continue;
+                }
+                weights[x] = 0;
+                picked.push(x);
+                visit(
+                    weights,
+                    bombs,
+                    probability * weight as f64 / sum,
+                    picked,
+                    outcomes,
+                );
+                picked.pop();
+                weights[x] = weight;
+            }
+        }
+
+        let mut outcomes = BTreeMap::new();
+        let bombs = bombs.min(self.generated.len());
+        visit(
+            &mut self.weights(),
+            bombs,
+            1.,
+            &mut Vec::new(),
+            &mut outcomes,
+        );
+        outcomes
+            .into_iter()
+            .map(|(bombs, probability)| (probability, row_with_bombs(self.generated.len(), &bombs)))
+            .collect()
+    }
+
+    fn reset(&mut self) {
+        self.generated.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn bombs(row: &[CellType]) -> Vec<usize> {
+        row.iter()
+            .enumerate()
+            .filter(|(_, &x)| x == CellType::Bomb)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(
+            combinations(0, 4, 2),
+            vec![
+                vec![0, 1],
+                vec![0, 2],
+                vec![0, 3],
+                vec![1, 2],
+                vec![1, 3],
+                vec![2, 3]
+            ]
+        );
+        assert_eq!(combinations(0, 3, 0), vec![Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn test_bag_generator() {
+        let mut generator = BagGenerator::new(8, 42);
+        let mut rows: Vec<_> = (0..28).map(|_| bombs(&generator.next_row(2))).collect();
+        rows.sort();
+        assert_eq!(rows, combinations(0, 8, 2));
+
+        assert_eq!(bombs(&generator.next_row(1)).len(), 1);
+    }
+
+    #[test]
+    fn test_spread_generator() {
+        let mut generator = SpreadGenerator::new(8, 42);
+        for _ in 0..100 {
+            let row = generator.next_row(2);
+            assert_eq!(row.len(), 8);
+            assert_eq!(bombs(&row).len(), 2);
+            let max = generator.generated.iter().max().unwrap();
+            let min = generator.generated.iter().min().unwrap();
+            assert!(max - min <= 4);
+        }
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let mut generator = BagGenerator::new(4, 3);
+        assert_eq!(generator.outcomes(2).len(), 6);
+        let row = generator.next_row(2);
+        let outcomes = generator.outcomes(2);
+        assert_eq!(outcomes.len(), 5);
+        assert!(outcomes.iter().all(|(_, x)| x != &row));
+
+        let mut generator = SpreadGenerator::new(4, 3);
+        generator.next_row(2);
+        let outcomes = generator.outcomes(2);
+        assert_eq!(outcomes.len(), 6);
+        let total: f64 = outcomes.iter().map(|x| x.0).sum();
+        assert!((total - 1.).abs() < 1e-9);
+        let (probability, row) = outcomes
+            .iter()
+            .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .unwrap();
+        assert!(*probability > 0.5);
+        assert!(row
+            .iter()
+            .zip(&generator.generated)
+            .all(|(&cell, &generated)| (cell == CellType::Bomb) == (generated == 0)));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        fn rows(mut generator: impl RowGenerator) -> Vec<Vec<usize>> {
+            (0..50)
+                .map(|i| bombs(&generator.next_row(i % 2 + 1)))
+                .collect()
+        }
+
+        assert_eq!(rows(BagGenerator::new(8, 7)), rows(BagGenerator::new(8, 7)));
+        assert_eq!(
+            rows(SpreadGenerator::new(8, 7)),
+            rows(SpreadGenerator::new(8, 7))
+        );
+        assert_ne!(
+            rows(SpreadGenerator::new(8, 7)),
+            rows(SpreadGenerator::new(8, 8))
+        );
+    }
+}
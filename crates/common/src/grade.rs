@@ -0,0 +1,189 @@
+use crate::chain::ChainReport;
+use crate::scoring::ScoringRule;
+
+pub struct Grade {
+    pub grade: &'static str,
+    /// Frames it takes to lose one point.
+    pub decay_rate: usize,
+    pub required_score: isize,
+    pub score_offset: usize,
+}
+
+impl Grade {
+    const fn new(
+        grade: &'static str,
+        decay_rate: usize,
+        required_score: isize,
+        score_offset: usize,
+    ) -> Grade {
+        Grade {
+            grade,
+            decay_rate,
+            required_score,
+            score_offset,
+        }
+    }
+}
+
+pub static RANKS: [Grade; 19] = [
+    Grade::new("C3", 120, 75, 0),
+    Grade::new("C2", 90, 75, 0),
+    Grade::new("C1", 90, 100, 0),
+    Grade::new("B3", 75, 85, 0),
+    Grade::new("B2", 75, 85, 0),
+    Grade::new("B1", 75, 130, 0),
+    Grade::new("A3", 60, 100, 1),
+    Grade::new("A2", 60, 100, 1),
+    Grade::new("A1", 60, 200, 1),
+    Grade::new("S1", 50, 200, 2),
+    Grade::new("S2", 45, 200, 2),
+    Grade::new("S3", 40, 200, 2),
+    Grade::new("S4", 35, 200, 3),
+    Grade::new("S5", 30, 200, 3),
+    Grade::new("S6", 25, 200, 3),
+    Grade::new("S7", 20, 200, 4),
+    Grade::new("S8", 15, 200, 4),
+    Grade::new("S9", 10, 250, 5),
+    Grade::new("master", 10, 1000000, 1000),
+];
+
+/// Normal mode ranks with the score each one starts at.
+pub const SCORE_RANKS: [(usize, &str); 10] = [
+    (0, "D"),
+    (5000, "D+"),
+    (10000, "C"),
+    (15000, "C+"),
+    (20000, "B"),
+    (25000, "B+"),
+    (30000, "A"),
+    (35000, "A+"),
+    (40000, "A++"),
+    (50000, "Awesome"),
+];
+
+pub fn score_rank(score: usize) -> &'static str {
+    let index = SCORE_RANKS
+        .binary_search_by_key(&(score + 1), |x| x.0)
+        .unwrap_or_else(|x| x)
+        - 1;
+    SCORE_RANKS[index].1
+}
+
+/// Rows between single-bomb rows in each master mode section.
+pub const SINGLE_FREQUENCY: [usize; 10] = [9999, 9, 8, 7, 6, 5, 4, 3, 2, 2];
+
+#[derive(Clone, Debug, Default)]
+pub struct GradeManager {
+    score: isize,
+    current_grade: usize,
+    max_chain_per_section: [usize; 10],
+    elapsed_frames: usize,
+}
+
+impl GradeManager {
+    pub fn new() -> GradeManager {
+        GradeManager::default()
+    }
+
+    /// Advances the clock by `frames` sixtieths of a second, losing a point every `decay_rate`
+    /// frames.
+    pub fn decay(&mut self, frames: usize) {
+        self.elapsed_frames += frames;
+
+        let decay_rate = self.current_grade().decay_rate;
+        let decay = self.elapsed_frames / decay_rate;
+        self.score -= decay as isize;
+        self.elapsed_frames -= decay * decay_rate;
+
+        if self.current_grade == 0 && self.score < 0 {
+            self.score = 0;
+        }
+    }
+
+    /// Scores a chain by `scoring`, returning whether it promoted the grade.
+    pub fn add(
+        &mut self,
+        section: usize,
+        report: &ChainReport,
+        scoring: &mut dyn ScoringRule,
+    ) -> bool {
+        if self.current_grade().grade == "S9" && section == 9 && self.max_chain_per_section[9] > 0 {
+            return false;
+        }
+
+        let bombs = report.bombs();
+        let score_offset = self.current_grade().score_offset;
+        let score = scoring.score(report, section).saturating_sub(score_offset);
+
+        self.score += score as isize;
+        self.max_chain_per_section[section] = self.max_chain_per_section[section].max(bombs);
+
+        let is_promoted = self.score >= self.current_grade().required_score;
+        while self.score >= self.current_grade().required_score {
+            if (section < 9 || !self.fulfills_master_condition())
+                && self.current_grade().grade == "S9"
+            {
+                return false;
+            }
+            self.score -= self.current_grade().required_score;
+            self.current_grade += 1;
+        }
+
+        is_promoted
+    }
+
+    pub fn score(&self) -> isize {
+        self.score
+    }
+
+    pub fn current_grade(&self) -> &'static Grade {
+        &RANKS[self.current_grade]
+    }
+
+    fn fulfills_master_condition(&self) -> bool {
+        let section_conditions = self.max_chain_per_section[0..=8].iter().all(|&x| x >= 5);
+        let current_grade = self.current_grade();
+        current_grade.grade == "S9"
+            && self.score >= current_grade.required_score
+            && section_conditions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{CellType, DynamicBoard, Grid};
+    use crate::scoring::Master;
+
+    fn chain(bombs: usize) -> ChainReport {
+        let mut board = DynamicBoard::new(bombs, 1);
+        board.feed(&vec![CellType::Bomb; bombs]);
+        board.remove(0, 0)
+    }
+
+    #[test]
+    fn test_grade() {
+        let mut grade = GradeManager::new();
+        assert!(!grade.add(0, &chain(4), &mut Master));
+        assert_eq!(grade.score(), 8);
+
+        grade.decay(119);
+        assert_eq!(grade.score(), 8);
+        grade.decay(1);
+        assert_eq!(grade.score(), 7);
+        grade.decay(1200);
+        assert_eq!(grade.score(), 0);
+
+        assert!(grade.add(4, &chain(9), &mut Master));
+        assert_eq!(grade.current_grade().grade, "C2");
+        assert_eq!(grade.score(), 27 * 3 - 75);
+    }
+
+    #[test]
+    fn test_score_rank() {
+        assert_eq!(score_rank(0), "D");
+        assert_eq!(score_rank(4999), "D");
+        assert_eq!(score_rank(5000), "D+");
+        assert_eq!(score_rank(1000000), "Awesome");
+    }
+}
@@ -0,0 +1,338 @@
+use crate::board::{Cell, CellType, Grid};
+use crate::chain::ChainReport;
+use serde::{Deserialize, Serialize};
+
+/// A cell moved by gravity, between `(x, y)` positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fall {
+    pub id: usize,
+    pub from: (usize, usize),
+    pub to: (usize, usize),
+}
+
+/// A board mutation with enough information to apply it again or take it back.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Operation {
+    Remove(ChainReport),
+    /// Falls in order of where they landed, column by column, top to bottom within a column.
+    Gravity(Vec<Fall>),
+    /// The fed row, and the top row it pushed off the board.
+    Feed {
+        row: Vec<Cell>,
+        pushed: Vec<Option<Cell>>,
+    },
+}
+
+impl Operation {
+    pub fn remove<G: Grid + ?Sized>(grid: &mut G, x: usize, y: usize) -> Self {
+        Operation::Remove(grid.remove(x, y))
+    }
+
+    pub fn gravity<G: Grid + ?Sized>(grid: &mut G) -> Self {
+        let fall_distance = grid.apply_gravity();
+        let mut falls = Vec::new();
+        for x in 0..grid.width() {
+            for y in 0..grid.height() {
+                if let Some(cell) = grid.get(x, y) {
+                    let down = fall_distance.down.get(&cell.id).copied().unwrap_or(0);
+                    let left = fall_distance.left.get(&cell.id).copied().unwrap_or(0);
+                    if down > 0 || left > 0 {
+                        falls.push(Fall {
+                            id: cell.id,
+                            from: (x + left, y - down),
+                            to: (x, y),
+                        });
+                    }
+                }
+            }
+        }
+
+        Operation::Gravity(falls)
+    }
+
+    pub fn feed<G: Grid + ?Sized>(grid: &mut G, row: &[CellType]) -> Self {
+        let pushed = (0..grid.width()).map(|x| grid.get(x, 0)).collect();
+        let row = grid.feed(row);
+        Operation::Feed { row, pushed }
+    }
+
+    /// Replays the operation on the board it was recorded from. A replayed feed reuses the
+    /// recorded cells, so ids stay the same and the id counter does not move.
+    pub fn apply<G: Grid + ?Sized>(&self, grid: &mut G) {
+        match self {
+            Operation::Remove(report) => {
+                for cell in &report.removed {
+                    grid.column_mut(cell.x)[cell.y] = None;
+                }
+                for cell in &report.damaged {
+                    grid.column_mut(cell.x)[cell.y] = Some(Cell {
+                        id: cell.id,
+                        cell_type: CellType::Tile,
+                    });
+                }
+            }
+            Operation::Gravity(falls) => {
+                let cells: Vec<_> = falls
+                    .iter()
+                    .map(|fall| grid.column_mut(fall.from.0)[fall.from.1].take())
+                    .collect();
+                for (fall, cell) in falls.iter().zip(cells) {
+                    grid.column_mut(fall.to.0)[fall.to.1] = cell;
+                }
+            }
+            Operation::Feed { row, .. } => {
+                for (x, &cell) in row.iter().enumerate() {
+                    let column = grid.column_mut(x);
+                    column.rotate_left(1);
+                    *column.last_mut().unwrap() = Some(cell);
+                }
+            }
+        }
+    }
+
+    /// Takes the operation back, assuming it was the last one applied to `grid`.
+    pub fn revert<G: Grid + ?Sized>(&self, grid: &mut G) {
+        match self {
+            Operation::Remove(report) => {
+                for cell in report.removed.iter().chain(report.damaged.iter()) {
+                    grid.column_mut(cell.x)[cell.y] = Some(Cell {
+                        id: cell.id,
+                        cell_type: cell.cell_type,
+                    });
+                }
+            }
+            Operation::Gravity(falls) => {
+                let cells: Vec<_> = falls
+                    .iter()
+                    .map(|fall| grid.column_mut(fall.to.0)[fall.to.1].take())
+                    .collect();
+                for (fall, cell) in falls.iter().zip(cells) {
+                    grid.column_mut(fall.from.0)[fall.from.1] = cell;
+                }
+            }
+            Operation::Feed { pushed, .. } => {
+                for (x, &cell) in pushed.iter().enumerate() {
+                    let column = grid.column_mut(x);
+                    column.rotate_right(1);
+                    column[0] = cell;
+                }
+            }
+        }
+    }
+}
+
+/// Undo and redo stacks of turns. A turn is every operation between two calls to `end_turn`, so
+/// a move and the gravity and feed that follow it are undone together.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    done: Vec<Vec<Operation>>,
+    undone: Vec<Vec<Operation>>,
+    turn: Vec<Operation>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records an operation that was just applied. Anything undone can no longer be redone.
+    pub fn push(&mut self, operation: Operation) {
+        self.undone.clear();
+        self.turn.push(operation);
+    }
+
+    pub fn end_turn(&mut self) {
+        if !self.turn.is_empty() {
+            self.done.push(std::mem::take(&mut self.turn));
+        }
+    }
+
+    /// Operations played and not undone, a turn at a time, oldest first.
+    pub fn turns(&self) -> impl Iterator<Item = &[Operation]> {
+        self.done
+            .iter()
+            .chain(Some(&self.turn).filter(|turn| !turn.is_empty()))
+            .map(Vec::as_slice)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.done.is_empty() || !self.turn.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.undone.is_empty()
+    }
+
+    /// Moves the last turn to the redo stack and returns it in the order it was played. The
+    /// caller reverts the operations, last first.
+    pub fn undo(&mut self) -> Option<&[Operation]> {
+        self.end_turn();
+        let turn = self.done.pop()?;
+        self.undone.push(turn);
+        self.undone.last().map(Vec::as_slice)
+    }
+
+    /// Moves the last undone turn back and returns it in the order it was played. The caller
+    /// applies the operations, first first.
+    pub fn redo(&mut self) -> Option<&[Operation]> {
+        self.end_turn();
+        let turn = self.undone.pop()?;
+        self.done.push(turn);
+        self.done.last().map(Vec::as_slice)
+    }
+
+    /// Reverts the last turn on `grid`.
+    pub fn undo_on<G: Grid + ?Sized>(&mut self, grid: &mut G) -> bool {
+        match self.undo() {
+            Some(turn) => {
+                for operation in turn.iter().rev() {
+                    operation.revert(grid);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replays the last undone turn on `grid`.
+    pub fn redo_on<G: Grid + ?Sized>(&mut self, grid: &mut G) -> bool {
+        match self.redo() {
+            Some(turn) => {
+                for operation in turn {
+                    operation.apply(grid);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{DynamicBoard, Gravity};
+    use CellType::*;
+
+    fn play(board: &mut DynamicBoard, history: &mut History, x: usize, y: usize) {
+        history.push(Operation::remove(board, x, y));
+        history.push(Operation::gravity(board));
+        history.push(Operation::feed(board, &[Bomb, Tile, Armored, Tile]));
+        history.end_turn();
+    }
+
+    #[test]
+    fn test_operations() {
+        let mut board: DynamicBoard = "
+            #...
+            #@#.
+            *#*#
+            ##@#
+        "
+        .parse()
+        .unwrap();
+        let before = board.cells().to_vec();
+
+        let operations = vec![
+            Operation::remove(&mut board, 0, 2),
+            Operation::gravity(&mut board),
+            Operation::feed(&mut board, &[Bomb, Tile, Armored, Tile]),
+        ];
+        let after = board.cells().to_vec();
+        assert_eq!(board.to_string(), "..#.\n..*#\n##@#\n*#@#");
+        assert_eq!(
+            operations[2],
+            Operation::Feed {
+                row: board.cells().iter().map(|x| x[3].unwrap()).collect(),
+                pushed: vec![None; 4],
+            }
+        );
+
+        for operation in operations.iter().rev() {
+            operation.revert(&mut board);
+        }
+        assert_eq!(board.cells(), before);
+
+        for operation in &operations {
+            operation.apply(&mut board);
+        }
+        assert_eq!(board.cells(), after);
+        assert_eq!(board.generated_cells(), 16);
+    }
+
+    #[test]
+    fn test_collapse() {
+        let mut board: DynamicBoard = "
+            *...
+            #.#.
+            #..*
+        "
+        .parse()
+        .unwrap();
+        board.gravity = Gravity::Collapse;
+        let before = board.cells().to_vec();
+
+        let operations = vec![
+            Operation::remove(&mut board, 0, 2),
+            Operation::remove(&mut board, 0, 1),
+            Operation::gravity(&mut board),
+        ];
+        let after = board.cells().to_vec();
+        let falls = match &operations[2] {
+            Operation::Gravity(falls) => falls.clone(),
+            _ => unreachable!(),
+        };
+        let moves: Vec<_> = falls.iter().map(|fall| (fall.from, fall.to)).collect();
+        assert_eq!(
+            moves,
+            [((0, 0), (0, 2)), ((2, 1), (1, 2)), ((3, 2), (2, 2))]
+        );
+
+        for operation in operations.iter().rev() {
+            operation.revert(&mut board);
+        }
+        assert_eq!(board.cells(), before);
+        for operation in &operations {
+            operation.apply(&mut board);
+        }
+        assert_eq!(board.cells(), after);
+    }
+
+    #[test]
+    fn test_history() {
+        let mut board: DynamicBoard = "
+            ....
+            *#..
+            #*##
+        "
+        .parse()
+        .unwrap();
+        let mut history = History::new();
+        assert!(!history.can_undo());
+
+        let start = board.cells().to_vec();
+        play(&mut board, &mut history, 0, 1);
+        let first = board.cells().to_vec();
+        play(&mut board, &mut history, 1, 2);
+        let second = board.cells().to_vec();
+
+        assert!(history.undo_on(&mut board));
+        assert_eq!(board.cells(), first);
+        assert_eq!(history.turns().count(), 1);
+        assert!(history.undo_on(&mut board));
+        assert_eq!(board.cells(), start);
+        assert_eq!(history.turns().count(), 0);
+        assert!(!history.undo_on(&mut board));
+
+        assert!(history.redo_on(&mut board));
+        assert_eq!(board.cells(), first);
+        assert!(history.redo_on(&mut board));
+        assert_eq!(board.cells(), second);
+        assert!(!history.can_redo());
+
+        history.undo_on(&mut board);
+        play(&mut board, &mut history, 3, 2);
+        assert!(!history.can_redo());
+        assert_ne!(board.cells(), second);
+    }
+}
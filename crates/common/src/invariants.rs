@@ -0,0 +1,248 @@
+//! Randomised checks of the board rules over every cell type and blast topology. Cases come from
+//! fixed seeds, so a failure names the seed and position that reproduce it.
+
+use crate::blast::BlastShape;
+use crate::board::{blast_area, Board, Cell, CellType, DynamicBoard, Grid};
+use crate::history::Operation;
+use crate::rng::Rng;
+use std::collections::BTreeSet;
+
+const CASES: u64 = 300;
+const STEPS: usize = 40;
+
+fn below(rng: &mut Rng, bound: usize) -> usize {
+    rng.below(bound as u64) as usize
+}
+
+fn random_cell_type(rng: &mut Rng) -> CellType {
+    CellType::ALL[below(rng, CellType::ALL.len())]
+}
+
+fn random_row(rng: &mut Rng, width: usize) -> Vec<CellType> {
+    (0..width).map(|_| random_cell_type(rng)).collect()
+}
+
+fn random_shape(rng: &mut Rng) -> BlastShape {
+    match below(rng, 6) {
+        0 => BlastShape::King,
+        1 => BlastShape::Orthogonal,
+        2 => BlastShape::Diamond(1 + below(rng, 3)),
+        3 => BlastShape::Plus(1 + below(rng, 3)),
+        4 => BlastShape::Knight,
+        _ => {
+            let offsets = (0..1 + below(rng, 5))
+                .map(|_| (below(rng, 5) as isize - 2, below(rng, 5) as isize - 2))
+                .filter(|&offset| offset != (0, 0))
+                .collect();
+            BlastShape::Custom(offsets)
+        }
+    }
+}
+
+/// A board with random holes, so cells may float until gravity is applied.
+fn random_board(rng: &mut Rng) -> DynamicBoard {
+    let (width, height) = (1 + below(rng, 8), 1 + below(rng, 10));
+    let mut board = DynamicBoard::new(width, height);
+    board.blast_shape = random_shape(rng);
+    for _ in 0..below(rng, height + 1) {
+        board.feed(&random_row(rng, width));
+    }
+    for x in 0..width {
+        for y in 0..height {
+            if below(rng, 4) == 0 {
+                board.column_mut(x)[y] = None;
+            }
+        }
+    }
+    board
+}
+
+fn cells<G: Grid>(grid: &G) -> Vec<(usize, usize, Cell)> {
+    (0..grid.width())
+        .flat_map(|x| (0..grid.height()).map(move |y| (x, y)))
+        .filter_map(|(x, y)| grid.get(x, y).map(|cell| (x, y, cell)))
+        .collect()
+}
+
+fn check_unique_ids<G: Grid>(grid: &G, context: &str) {
+    let ids: BTreeSet<_> = cells(grid).iter().map(|&(_, _, cell)| cell.id).collect();
+    assert_eq!(ids.len(), cells(grid).len(), "duplicate ids, {context}");
+}
+
+fn check_remove<G: Grid + Clone>(grid: &mut G, x: usize, y: usize, context: &str) {
+    let before = grid.clone();
+    let expected = before
+        .moves()
+        .into_iter()
+        .find(|m| m.triggers.contains(&(x, y)));
+    let report = grid.remove(x, y);
+
+    let hit: BTreeSet<_> = report
+        .removed
+        .iter()
+        .chain(report.damaged.iter())
+        .map(|cell| (cell.x, cell.y))
+        .collect();
+    for (cx, cy) in (0..grid.width()).flat_map(|x| (0..grid.height()).map(move |y| (x, y))) {
+        if !hit.contains(&(cx, cy)) {
+            assert_eq!(
+                grid.get(cx, cy),
+                before.get(cx, cy),
+                "({cx}, {cy}) changed outside the blast, {context}"
+            );
+        }
+    }
+
+    for cell in &report.removed {
+        let old = before.get(cell.x, cell.y).unwrap();
+        assert_eq!(
+            (old.id, old.cell_type),
+            (cell.id, cell.cell_type),
+            "{context}"
+        );
+        assert_ne!(old.cell_type, CellType::Armored, "{context}");
+        assert_eq!(grid.get(cell.x, cell.y), None, "{context}");
+    }
+    for cell in &report.damaged {
+        assert_eq!(cell.cell_type, CellType::Armored, "{context}");
+        assert_eq!(
+            grid.get(cell.x, cell.y),
+            Some(Cell {
+                id: cell.id,
+                cell_type: CellType::Tile,
+            }),
+            "{context}"
+        );
+    }
+
+    // Every hit after the trigger lies in the blast of a cell removed one wave earlier.
+    for cell in report.removed.iter().chain(report.damaged.iter()) {
+        if cell.distance == 0 {
+            assert_eq!((cell.x, cell.y), (x, y), "{context}");
+            continue;
+        }
+        let reached = report
+            .removed
+            .iter()
+            .filter(|source| source.distance + 1 == cell.distance)
+            .any(|source| {
+                blast_area(
+                    source.cell_type,
+                    before.blast_shape(),
+                    source.x,
+                    source.y,
+                    before.width(),
+                    before.height(),
+                )
+                .contains(&(cell.x, cell.y))
+            });
+        assert!(
+            reached,
+            "({}, {}) hit from nowhere, {context}",
+            cell.x, cell.y
+        );
+    }
+
+    match expected {
+        Some(m) => {
+            let removed: BTreeSet<_> = report.removed.iter().map(|c| (c.x, c.y)).collect();
+            let damaged: BTreeSet<_> = report.damaged.iter().map(|c| (c.x, c.y)).collect();
+            assert_eq!(removed, m.removed.into_iter().collect(), "{context}");
+            assert_eq!(damaged, m.damaged.into_iter().collect(), "{context}");
+        }
+        None => assert!(report.is_empty(), "{context}"),
+    }
+
+    let mut reverted = grid.clone();
+    Operation::Remove(report).revert(&mut reverted);
+    assert_eq!(cells(&reverted), cells(&before), "revert failed, {context}");
+}
+
+fn check_gravity<G: Grid + Clone>(grid: &mut G, context: &str) {
+    let columns = |grid: &G| -> Vec<Vec<Cell>> {
+        (0..grid.width())
+            .map(|x| grid.column(x).iter().flatten().cloned().collect())
+            .collect()
+    };
+    let before = columns(grid);
+    let fall_distance = grid.apply_gravity();
+    assert_eq!(columns(grid), before, "gravity reordered cells, {context}");
+
+    for x in 0..grid.width() {
+        let column = grid.column(x);
+        let first = column
+            .iter()
+            .position(Option::is_some)
+            .unwrap_or(column.len());
+        assert!(
+            column[first..].iter().all(Option::is_some),
+            "column {x} has a floating cell, {context}"
+        );
+    }
+    for &dist in fall_distance.down.values() {
+        assert!(dist > 0 && dist < grid.height(), "{context}");
+    }
+    assert!(fall_distance.left.is_empty(), "{context}");
+}
+
+fn check_feed<G: Grid + Clone>(grid: &mut G, row: &[CellType], context: &str) {
+    let count = cells(grid).len();
+    let pushed_off = (0..grid.width())
+        .filter(|&x| grid.get(x, 0).is_some())
+        .count();
+    let max_id = cells(grid).iter().map(|&(_, _, cell)| cell.id).max();
+
+    let fed = grid.feed(row);
+    assert_eq!(
+        cells(grid).len(),
+        count + grid.width() - pushed_off,
+        "{context}"
+    );
+    let bottom = grid.height() - 1;
+    for (x, cell) in fed.iter().enumerate() {
+        assert_eq!(grid.get(x, bottom), Some(*cell), "{context}");
+        assert_eq!(cell.cell_type, row[x], "{context}");
+        assert!(max_id.is_none_or(|id| cell.id > id), "{context}");
+    }
+}
+
+fn run<G: Grid + Clone>(grid: &mut G, rng: &mut Rng, seed: u64) {
+    for step in 0..STEPS {
+        let context = format!("seed {seed}, step {step}, board:\n{}", grid.to_notation());
+        match below(rng, 3) {
+            0 => {
+                let (x, y) = (below(rng, grid.width()), below(rng, grid.height()));
+                check_remove(grid, x, y, &context);
+            }
+            1 => check_gravity(grid, &context),
+            _ => {
+                let row = random_row(rng, grid.width());
+                check_feed(grid, &row, &context);
+            }
+        }
+        check_unique_ids(grid, &context);
+    }
+}
+
+#[test]
+fn test_dynamic_board() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let mut board = random_board(&mut rng);
+        run(&mut board, &mut rng, seed);
+    }
+}
+
+#[test]
+fn test_const_board() {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let mut board = DynamicBoard::new(8, 9);
+        board.blast_shape = random_shape(&mut rng);
+        for _ in 0..below(&mut rng, 9) {
+            board.feed(&random_row(&mut rng, 8));
+        }
+        let mut board = Board::<8, 9>::try_from(board).unwrap();
+        run(&mut board, &mut rng, seed);
+    }
+}
@@ -1,2 +1,19 @@
+pub mod bitboard;
+pub mod blast;
 pub mod board;
+pub mod chain;
+pub mod engine;
+pub mod garbage;
+pub mod generator;
+pub mod grade;
+pub mod history;
+#[cfg(test)]
+mod invariants;
 pub mod model;
+pub mod puzzle;
+pub mod puzzle_generator;
+pub mod replay;
+pub mod rng;
+pub mod scoring;
+pub mod solver;
+pub mod zobrist;
@@ -1,17 +1,101 @@
+use crate::board::{CellType, DynamicBoard};
+use crate::garbage::{Attack, Targeting};
 use serde::{Serialize, Deserialize};
 
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(tag = "type")]
 pub enum RequestMessage {
+    /// Queues for a match with anyone else queueing.
     Join,
+    /// Leaves the queue or the room the player is in.
     Leave,
     Remove { x: usize, y: usize },
+    /// Opens a private room, whose code comes back in a `Room` response.
+    CreateRoom,
+    JoinRoom { code: String },
+    /// The room's game starts once everyone in it is ready.
+    Ready,
+    /// Chooses who the player's attacks go to.
+    Target { targeting: Targeting },
+    /// Asks for the matches being played, answered with `Games`.
+    ListGames,
+    /// Watches a match from `Games` without playing in it. `Leave` stops watching.
+    Spectate { game: usize },
+    /// Takes back the board of a match the player lost their connection to, answered with
+    /// `Resumed` or `SessionExpired`.
+    Resume { session: String },
 }
 
 #[derive(Deserialize, Serialize, Clone)]
 #[serde(tag = "type")]
 pub enum ResponseMessage {
-    Ready,
+    /// The match started. Players are numbered from 0, and `player` is the receiver. Every board
+    /// in the match is `width` by `height`. `session` resumes the match after a dropped
+    /// connection.
+    Ready {
+        player: usize,
+        players: usize,
+        width: usize,
+        height: usize,
+        session: String,
+    },
     Remove { x: usize, y: usize },
-    Feed { row: Vec<bool> },
+    Feed { row: Vec<CellType> },
+    /// The player's score, counted by the server, after a remove.
+    Score { score: usize },
+    /// The garbage waiting to be fed to the player changed.
+    Garbage { pending: Attack },
+    /// What was left of the player's attack after cancelling their own garbage, and who it went
+    /// to.
+    Sent { attack: Attack, target: usize },
+    /// An opponent set off a chain, as `Remove` does for the player's own board. Spectators get
+    /// these for every player.
+    OpponentRemove { player: usize, x: usize, y: usize },
+    /// A row was fed to an opponent, as `Feed` is to the player.
+    OpponentFeed { player: usize, row: Vec<CellType> },
+    /// An opponent's board changed. Holds the height of each of its columns.
+    Stack { player: usize, heights: Vec<usize> },
+    /// A player topped out or left and finished in `place`, or won the match in first place.
+    Placement { player: usize, place: usize },
+    /// The player topped out.
+    Lose,
+    /// The player outlasted everyone else.
+    Win,
+    /// Time ran out with the player still in.
+    Draw,
+    /// The match ended for everyone. Nothing more is sent about it.
+    GameOver,
+    Games { games: Vec<GameInfo> },
+    /// Every board of the match a spectator started watching, which `OpponentRemove` and
+    /// `OpponentFeed` keep up to date from then on.
+    Snapshot { boards: Vec<BoardSnapshot> },
+    /// The player is back in their match, which is as these boards show.
+    Resumed {
+        player: usize,
+        players: usize,
+        boards: Vec<BoardSnapshot>,
+        score: usize,
+        pending: Attack,
+    },
+    /// The match is over, or the player was out of it for too long.
+    SessionExpired,
+    /// The room the player is in changed.
+    Room { code: String, players: usize, ready: usize },
+    /// No room has this code, or it is full.
+    RoomUnavailable { code: String },
+}
+
+/// A match being played, as listed to spectators.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct GameInfo {
+    pub id: usize,
+    pub players: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct BoardSnapshot {
+    pub player: usize,
+    pub board: DynamicBoard,
+    /// Where the player finished, if they are out.
+    pub place: Option<usize>,
 }
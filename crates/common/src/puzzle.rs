@@ -0,0 +1,371 @@
+use crate::blast::BlastShape;
+use crate::board::{BoardError, CellType, DynamicBoard, Grid};
+use crate::history::Operation;
+use crate::scoring::triangular;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What a puzzle asks for. Scores are counted as in normal mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Goal {
+    /// Remove every bomb within `moves` moves.
+    ClearBombs { moves: usize },
+    /// Score at least `score` points before the board tops out or empties.
+    Score { score: usize },
+    /// Take `feeds` rows without topping out.
+    Survive { feeds: usize },
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::ClearBombs { moves: 1 } => write!(f, "Clear every bomb in 1 move"),
+            Goal::ClearBombs { moves } => write!(f, "Clear every bomb in {moves} moves"),
+            Goal::Score { score } => write!(f, "Score {score} points"),
+            Goal::Survive { feeds: 1 } => write!(f, "Survive 1 row"),
+            Goal::Survive { feeds } => write!(f, "Survive {feeds} rows"),
+        }
+    }
+}
+
+/// A hand-made position. Rows use the board notation, top row first. After every move the next
+/// incoming row is fed, and once they run out no more rows arrive.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Puzzle {
+    /// Identifies the puzzle in saved completion state, so it must not change once published.
+    pub id: String,
+    pub title: String,
+    pub board: Vec<String>,
+    #[serde(default)]
+    pub incoming: Vec<String>,
+    pub goal: Goal,
+    #[serde(default)]
+    pub blast_shape: BlastShape,
+    /// From 1 to 5, as estimated by `puzzle_generator::analyze`.
+    #[serde(default)]
+    pub difficulty: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PuzzlePack {
+    pub name: String,
+    pub puzzles: Vec<Puzzle>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PuzzleError {
+    Board(BoardError),
+    /// An incoming row has an empty cell or does not match the board width.
+    IncomingRow(usize),
+    /// A survival goal asks for more feeds than there are incoming rows.
+    NotEnoughRows {
+        feeds: usize,
+        rows: usize,
+    },
+    /// The board starts out full to the top.
+    FilledBoard,
+}
+
+impl fmt::Display for PuzzleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PuzzleError::Board(error) => write!(f, "invalid board: {error}"),
+            PuzzleError::IncomingRow(index) => write!(f, "incoming row {index} is invalid"),
+            PuzzleError::NotEnoughRows { feeds, rows } => {
+                write!(
+                    f,
+                    "surviving {feeds} feeds needs as many rows, found {rows}"
+                )
+            }
+            PuzzleError::FilledBoard => write!(f, "the board starts topped out"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+impl From<BoardError> for PuzzleError {
+    fn from(error: BoardError) -> Self {
+        PuzzleError::Board(error)
+    }
+}
+
+impl Puzzle {
+    pub fn to_board(&self) -> Result<DynamicBoard, PuzzleError> {
+        let mut board: DynamicBoard = self.board.join("\n").parse()?;
+        board.blast_shape = self.blast_shape.clone();
+        Ok(board)
+    }
+
+    pub fn incoming_rows(&self) -> Result<Vec<Vec<CellType>>, PuzzleError> {
+        let width = self.board.first().map_or(0, |row| row.chars().count());
+        self.incoming
+            .iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let row = row
+                    .chars()
+                    .map(CellType::from_char)
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(PuzzleError::IncomingRow(index))?;
+                if row.len() == width {
+                    Ok(row)
+                } else {
+                    Err(PuzzleError::IncomingRow(index))
+                }
+            })
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), PuzzleError> {
+        let board = self.to_board()?;
+        let rows = self.incoming_rows()?.len();
+        match self.goal {
+            Goal::Survive { feeds } if feeds > rows => {
+                Err(PuzzleError::NotEnoughRows { feeds, rows })
+            }
+            _ if board.is_filled() => Err(PuzzleError::FilledBoard),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Playing,
+    Solved,
+    Failed,
+}
+
+/// A puzzle being played. It applies the moves to its own board and reports them as operations,
+/// so a view can replay them on a copy of the board.
+#[derive(Clone)]
+pub struct PuzzleGame {
+    pub board: DynamicBoard,
+    pub goal: Goal,
+    incoming: Vec<Vec<CellType>>,
+    pub moves: usize,
+    pub score: usize,
+    pub feeds: usize,
+}
+
+impl PuzzleGame {
+    pub fn new(puzzle: &Puzzle) -> Result<Self, PuzzleError> {
+        puzzle.validate()?;
+        Ok(PuzzleGame {
+            board: puzzle.to_board()?,
+            goal: puzzle.goal,
+            incoming: puzzle.incoming_rows()?,
+            moves: 0,
+            score: 0,
+            feeds: 0,
+        })
+    }
+
+    /// The rows still to be fed, next first.
+    pub fn incoming(&self) -> &[Vec<CellType>] {
+        &self.incoming[self.feeds..]
+    }
+
+    fn bombs_left(&self) -> bool {
+        (0..self.board.width()).any(|x| {
+            self.board
+                .column(x)
+                .iter()
+                .flatten()
+                .any(|cell| cell.cell_type.is_bomb())
+        })
+    }
+
+    /// Full to the top with rows still to come. Once they run out, a full board is safe.
+    fn is_topped_out(&self) -> bool {
+        self.board.is_filled() && !self.incoming().is_empty()
+    }
+
+    fn is_empty(&self) -> bool {
+        (0..self.board.width()).all(|x| self.board.column(x).iter().all(Option::is_none))
+    }
+
+    pub fn status(&self) -> Status {
+        match self.goal {
+            Goal::ClearBombs { moves } => {
+                if !self.bombs_left() {
+                    Status::Solved
+                } else if self.moves >= moves || self.is_topped_out() {
+                    Status::Failed
+                } else {
+                    Status::Playing
+                }
+            }
+            Goal::Score { score } => {
+                if self.score >= score {
+                    Status::Solved
+                } else if self.is_topped_out() || self.is_empty() {
+                    Status::Failed
+                } else {
+                    Status::Playing
+                }
+            }
+            Goal::Survive { feeds } => {
+                if self.feeds >= feeds {
+                    Status::Solved
+                } else if self.is_topped_out() || self.is_empty() {
+                    Status::Failed
+                } else {
+                    Status::Playing
+                }
+            }
+        }
+    }
+
+    /// Detonates `(x, y)`, lets the board fall and feeds the next incoming row. Returns the
+    /// operations applied, or nothing if the puzzle is over or the cell is empty.
+    pub fn play(&mut self, x: usize, y: usize) -> Vec<Operation> {
+        if self.status() != Status::Playing {
+            return Vec::new();
+        }
+        let report = self.board.remove(x, y);
+        if report.is_empty() {
+            return Vec::new();
+        }
+
+        self.moves += 1;
+        self.score += triangular(report.removed_count());
+        let mut operations = vec![
+            Operation::Remove(report),
+            Operation::gravity(&mut self.board),
+        ];
+        if let Some(row) = self.incoming.get(self.feeds) {
+            operations.push(Operation::feed(&mut self.board, row));
+            self.feeds += 1;
+        }
+        operations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn puzzle(board: &[&str], incoming: &[&str], goal: Goal) -> Puzzle {
+        Puzzle {
+            id: "test".to_string(),
+            title: "Test".to_string(),
+            board: board.iter().map(|row| row.to_string()).collect(),
+            incoming: incoming.iter().map(|row| row.to_string()).collect(),
+            goal,
+            blast_shape: BlastShape::default(),
+            difficulty: None,
+        }
+    }
+
+    #[test]
+    fn test_clear_bombs() {
+        let puzzle = puzzle(
+            &["....", "....", "*..*", "#*##"],
+            &["####"],
+            Goal::ClearBombs { moves: 2 },
+        );
+        let mut game = PuzzleGame::new(&puzzle).unwrap();
+        assert!(game.play(0, 0).is_empty());
+
+        let operations = game.play(1, 3);
+        assert_eq!(operations.len(), 3);
+        assert_eq!(game.status(), Status::Playing);
+        assert_eq!(game.incoming(), &[] as &[Vec<CellType>]);
+
+        game.play(3, 1);
+        assert_eq!((game.moves, game.status()), (2, Status::Solved));
+        assert!(game.play(0, 3).is_empty());
+
+        let mut game = PuzzleGame::new(&puzzle).unwrap();
+        game.play(0, 3);
+        game.play(0, 3);
+        assert_eq!(game.status(), Status::Failed);
+    }
+
+    #[test]
+    fn test_score_and_survive() {
+        let mut game = PuzzleGame::new(&puzzle(
+            &["...", "#*#", "###"],
+            &[],
+            Goal::Score { score: 21 },
+        ))
+        .unwrap();
+        game.play(1, 1);
+        assert_eq!((game.score, game.status()), (21, Status::Solved));
+
+        let mut game = PuzzleGame::new(&puzzle(
+            &["...", "#..", "#*#"],
+            &["###", "*##"],
+            Goal::Survive { feeds: 2 },
+        ))
+        .unwrap();
+        game.play(0, 1);
+        assert_eq!(game.status(), Status::Playing);
+        game.play(2, 2);
+        assert_eq!((game.feeds, game.status()), (2, Status::Solved));
+    }
+
+    #[test]
+    fn test_errors() {
+        let invalid = puzzle(&["#.", "##"], &["#."], Goal::Survive { feeds: 1 });
+        assert_eq!(invalid.validate(), Err(PuzzleError::IncomingRow(0)));
+        let invalid = puzzle(&["#.", "#"], &[], Goal::Score { score: 1 });
+        assert_eq!(
+            invalid.validate(),
+            Err(PuzzleError::Board(BoardError::RaggedRows))
+        );
+        let invalid = puzzle(&["#.", "##"], &["##"], Goal::Survive { feeds: 2 });
+        assert_eq!(
+            invalid.validate(),
+            Err(PuzzleError::NotEnoughRows { feeds: 2, rows: 1 })
+        );
+        let filled = puzzle(&[".#", "##"], &[], Goal::Score { score: 1 });
+        assert_eq!(filled.validate(), Err(PuzzleError::FilledBoard));
+
+        // Filling the board with the last incoming row is not a top-out.
+        let mut game =
+            PuzzleGame::new(&puzzle(&["..", "##"], &["##"], Goal::Score { score: 9 })).unwrap();
+        game.play(0, 1);
+        assert!(game.board.is_filled());
+        assert_eq!(game.status(), Status::Playing);
+
+        let json = r###"{
+            "name": "Pack",
+            "puzzles": [{
+                "id": "a",
+                "title": "A",
+                "board": ["..", "#*"],
+                "goal": { "type": "ClearBombs", "moves": 1 }
+            }]
+        }"###;
+        let pack: PuzzlePack = serde_json::from_str(json).unwrap();
+        assert_eq!(pack.puzzles[0].goal, Goal::ClearBombs { moves: 1 });
+        assert!(pack.puzzles[0].validate().is_ok());
+    }
+
+    #[test]
+    fn test_bundled_packs() {
+        use crate::puzzle_generator::analyze;
+        use std::collections::HashSet;
+        use std::path::Path;
+
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../client/puzzles");
+        let read = |file: &str| std::fs::read_to_string(dir.join(file)).unwrap();
+        let index: Vec<String> = serde_json::from_str(&read("index.json")).unwrap();
+        assert!(!index.is_empty());
+
+        let mut ids = HashSet::new();
+        for file in &index {
+            let pack: PuzzlePack = serde_json::from_str(&read(file)).unwrap();
+            for puzzle in &pack.puzzles {
+                assert!(ids.insert(puzzle.id.clone()), "{}", puzzle.id);
+                assert_eq!(puzzle.validate(), Ok(()), "{}", puzzle.id);
+                let analysis = analyze(puzzle, 6, 1).unwrap();
+                assert!(analysis.is_solvable(), "{}", puzzle.id);
+            }
+        }
+    }
+}
@@ -0,0 +1,319 @@
+//! Builds clear-the-bombs puzzles from fed rows and checks them by exhaustive search.
+
+use crate::blast::BlastShape;
+use crate::board::{CellType, DynamicBoard, Grid};
+use crate::generator::{BagGenerator, RowGenerator};
+use crate::history::Operation;
+use crate::puzzle::{Goal, Puzzle, PuzzleError, PuzzleGame, Status};
+use crate::rng::Rng;
+use std::collections::HashSet;
+
+/// What an exhaustive search of a puzzle found.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    /// Solutions of the shortest length, counted up to the search limit. Playing the same moves
+    /// in another order, when the order changes nothing, is the same solution.
+    pub solutions: usize,
+    /// The first shortest solution found, as the cells detonated.
+    pub solution: Option<Vec<(usize, usize)>>,
+    /// Positions visited over every iteration of the search.
+    pub nodes: usize,
+    /// Average number of distinct moves in the positions that were expanded.
+    pub branching: f64,
+}
+
+impl Analysis {
+    pub fn is_solvable(&self) -> bool {
+        self.solution.is_some()
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.solutions == 1
+    }
+
+    /// A rating from 1 to 5, growing with the number of lines a player would have to read before
+    /// finding a solution.
+    pub fn difficulty(&self) -> usize {
+        let depth = self.solution.as_ref().map_or(0, Vec::len) as f64;
+        let lines = depth * self.branching.max(1.).ln() - (self.solutions.max(1) as f64).ln();
+        (1 + (lines / 2.5).max(0.) as usize).min(5)
+    }
+}
+
+struct Search {
+    limit: usize,
+    nodes: usize,
+    expanded: usize,
+    branches: usize,
+    /// States known to lead to no solution within the current depth.
+    dead: HashSet<(u64, usize, usize, usize)>,
+    /// Each solution as the sorted ids of the cells every move removed, sorted.
+    solutions: HashSet<Vec<Vec<usize>>>,
+    solution: Option<Vec<(usize, usize)>>,
+    path: Vec<(usize, usize)>,
+    removed: Vec<Vec<usize>>,
+}
+
+impl Search {
+    /// Counts the solutions under `game` that take at most `depth` more moves.
+    fn run(&mut self, game: &PuzzleGame, depth: usize) -> usize {
+        self.nodes += 1;
+        match game.status() {
+            Status::Solved => {
+                if self.solution.is_none() {
+                    self.solution = Some(self.path.clone());
+                }
+                let mut removed = self.removed.clone();
+                removed.sort();
+                self.solutions.insert(removed);
+                return 1;
+            }
+            Status::Failed => return 0,
+            Status::Playing if depth == 0 => return 0,
+            Status::Playing => {}
+        }
+
+        let key = (
+            game.board.position_hash(),
+            game.moves,
+            game.feeds,
+            game.score,
+        );
+        if self.dead.contains(&key) {
+            return 0;
+        }
+
+        let moves = game.board.moves();
+        self.expanded += 1;
+        self.branches += moves.len();
+        let mut found = 0;
+        for m in moves {
+            if self.solutions.len() >= self.limit {
+                break;
+            }
+            let mut next = game.clone();
+            let removed = match next.play(m.x, m.y).first() {
+                Some(Operation::Remove(report)) => {
+                    let mut ids: Vec<_> = report.removed.iter().map(|cell| cell.id).collect();
+                    ids.sort_unstable();
+                    ids
+                }
+                _ => continue,
+            };
+            self.path.push((m.x, m.y));
+            self.removed.push(removed);
+            found += self.run(&next, depth - 1);
+            self.path.pop();
+            self.removed.pop();
+        }
+        if found == 0 && self.solutions.len() < self.limit {
+            self.dead.insert(key);
+        }
+        found
+    }
+}
+
+/// Searches `puzzle` by iterative deepening. Clearing bombs and surviving are bounded by the goal,
+/// while score goals are searched `max_moves` deep. Counting stops at `limit` solutions, so a
+/// limit of 2 is enough to tell whether the solution is unique.
+pub fn analyze(puzzle: &Puzzle, max_moves: usize, limit: usize) -> Result<Analysis, PuzzleError> {
+    let game = PuzzleGame::new(puzzle)?;
+    let depth = match puzzle.goal {
+        Goal::ClearBombs { moves } => moves.min(max_moves),
+        Goal::Survive { feeds } => feeds.min(max_moves),
+        Goal::Score { .. } => max_moves,
+    };
+
+    let mut search = Search {
+        limit: limit.max(1),
+        nodes: 0,
+        expanded: 0,
+        branches: 0,
+        dead: HashSet::new(),
+        solutions: HashSet::new(),
+        solution: None,
+        path: Vec::new(),
+        removed: Vec::new(),
+    };
+    for depth in 0..=depth {
+        search.dead.clear();
+        search.run(&game, depth);
+        if search.solution.is_some() {
+            break;
+        }
+    }
+
+    Ok(Analysis {
+        solutions: search.solutions.len(),
+        solution: search.solution,
+        nodes: search.nodes,
+        branching: search.branches as f64 / search.expanded.max(1) as f64,
+    })
+}
+
+/// Makes puzzles whose goal is to clear every bomb in exactly `moves` moves. Candidates are
+/// built by feeding rows to an empty board, and kept only if no shorter solution exists.
+#[derive(Clone, Debug)]
+pub struct PuzzleGenerator {
+    pub width: usize,
+    pub height: usize,
+    /// Rows fed to build each candidate.
+    pub rows: usize,
+    /// Bombs in every fed row.
+    pub bombs: usize,
+    /// Tiles in every fed row that are turned into armored tiles.
+    pub armored: usize,
+    pub moves: usize,
+    pub unique: bool,
+    pub blast_shape: BlastShape,
+}
+
+impl PuzzleGenerator {
+    pub fn new(width: usize, height: usize) -> Self {
+        PuzzleGenerator {
+            width,
+            height,
+            rows: 4,
+            bombs: 2,
+            armored: 0,
+            moves: 2,
+            unique: true,
+            blast_shape: BlastShape::default(),
+        }
+    }
+
+    fn candidate(&self, seed: u64) -> Puzzle {
+        let mut generator = BagGenerator::new(self.width, seed);
+        let mut rng = Rng::new(seed);
+        let mut board = DynamicBoard::new(self.width, self.height);
+        board.blast_shape = self.blast_shape.clone();
+        for _ in 0..self.rows.min(self.height - 1) {
+            let mut row = generator.next_row(self.bombs);
+            let mut tiles: Vec<_> = (0..self.width)
+                .filter(|&x| row[x] == CellType::Tile)
+                .collect();
+            rng.shuffle(&mut tiles);
+            for &x in tiles.iter().take(self.armored) {
+                row[x] = CellType::Armored;
+            }
+            board.feed(&row);
+        }
+
+        Puzzle {
+            id: format!("generated-{seed}"),
+            title: format!("Puzzle {seed}"),
+            board: board.to_notation().lines().map(str::to_string).collect(),
+            incoming: Vec::new(),
+            goal: Goal::ClearBombs { moves: self.moves },
+            blast_shape: self.blast_shape.clone(),
+            difficulty: None,
+        }
+    }
+
+    /// Checks the candidate built from `seed`, returning it with its difficulty if it qualifies.
+    pub fn try_seed(&self, seed: u64) -> Option<Puzzle> {
+        let mut puzzle = self.candidate(seed);
+        let analysis = analyze(&puzzle, self.moves, 2).ok()?;
+        let length = analysis.solution.as_ref()?.len();
+        if length != self.moves || (self.unique && !analysis.is_unique()) {
+            return None;
+        }
+        puzzle.difficulty = Some(analysis.difficulty());
+        Some(puzzle)
+    }
+
+    /// Tries up to `attempts` seeds starting at `seed` and returns at most `count` puzzles, none
+    /// of which share a starting position.
+    pub fn generate(&self, seed: u64, count: usize, attempts: usize) -> Vec<Puzzle> {
+        let mut seen = HashSet::new();
+        let mut puzzles = Vec::new();
+        for seed in seed..seed + attempts as u64 {
+            if puzzles.len() >= count {
+                break;
+            }
+            if let Some(puzzle) = self.try_seed(seed) {
+                let hash = puzzle.to_board().map_or(0, |board| board.position_hash());
+                if seen.insert(hash) {
+                    puzzles.push(puzzle);
+                }
+            }
+        }
+        puzzles
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn puzzle(board: &[&str], goal: Goal) -> Puzzle {
+        Puzzle {
+            id: "test".to_string(),
+            title: "Test".to_string(),
+            board: board.iter().map(|row| row.to_string()).collect(),
+            incoming: Vec::new(),
+            goal,
+            blast_shape: BlastShape::default(),
+            difficulty: None,
+        }
+    }
+
+    #[test]
+    fn test_analyze() {
+        let single = puzzle(&["....", "..*.", "####"], Goal::ClearBombs { moves: 3 });
+        let analysis = analyze(&single, 3, 2).unwrap();
+        assert_eq!(analysis.solution, Some(vec![(2, 1)]));
+        assert!(analysis.is_unique());
+        assert_eq!(analysis.difficulty(), 1);
+
+        // Either bomb can go first, which is still one solution.
+        let two = puzzle(&[".....", "*...*", "#####"], Goal::ClearBombs { moves: 2 });
+        let analysis = analyze(&two, 2, 10).unwrap();
+        assert_eq!(analysis.solution.map(|x| x.len()), Some(2));
+        assert_eq!(analysis.solutions, 1);
+
+        // Any of the three tiles survives the feed.
+        let survive = Puzzle {
+            incoming: vec!["###".to_string()],
+            ..puzzle(&["...", "#..", "##."], Goal::Survive { feeds: 1 })
+        };
+        let analysis = analyze(&survive, 5, 10).unwrap();
+        assert_eq!(analysis.solutions, 3);
+        assert_eq!(analyze(&survive, 5, 2).unwrap().solutions, 2);
+
+        let impossible = puzzle(&[".....", "*...*", "#####"], Goal::ClearBombs { moves: 1 });
+        assert!(!analyze(&impossible, 5, 2).unwrap().is_solvable());
+    }
+
+    #[test]
+    fn test_generate() {
+        let mut generator = PuzzleGenerator::new(6, 7);
+        generator.rows = 3;
+        generator.moves = 2;
+        let puzzles = generator.generate(0, 3, 200);
+        assert!(!puzzles.is_empty());
+
+        let hashes: HashSet<_> = puzzles
+            .iter()
+            .map(|puzzle| puzzle.to_board().unwrap().position_hash())
+            .collect();
+        assert_eq!(hashes.len(), puzzles.len());
+        for puzzle in &puzzles {
+            let analysis = analyze(puzzle, 2, 2).unwrap();
+            assert!(analysis.is_unique(), "{}", puzzle.id);
+            assert_eq!(analysis.solution.as_ref().unwrap().len(), 2);
+            assert!(!analyze(
+                &Puzzle {
+                    goal: Goal::ClearBombs { moves: 1 },
+                    ..puzzle.clone()
+                },
+                1,
+                1
+            )
+            .unwrap()
+            .is_solvable());
+            assert_eq!(puzzle.difficulty, Some(analysis.difficulty()));
+        }
+        assert_eq!(generator.generate(0, 3, 200), puzzles);
+    }
+}
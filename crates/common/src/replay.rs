@@ -0,0 +1,262 @@
+use crate::blast::BlastShape;
+use crate::board::{Gravity, DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use crate::engine::GameState;
+use crate::scoring::{Scoring, ScoringRule};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const REPLAY_VERSION: u32 = 1;
+/// The widest or tallest board a replay may be played on.
+pub const MAX_BOARD_SIZE: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Mode {
+    Normal,
+    Master,
+}
+
+impl Mode {
+    /// How the mode scores chains unless the rules pick another way.
+    pub fn scoring(self) -> Scoring {
+        match self {
+            Mode::Normal => Scoring::Triangular,
+            Mode::Master => Scoring::Master,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    pub width: usize,
+    pub height: usize,
+    /// Normal mode ends once this many bombs are removed.
+    pub bombs_limit: usize,
+    /// Master mode ends at this level.
+    pub level_limit: usize,
+    #[serde(default)]
+    pub blast_shape: BlastShape,
+    #[serde(default)]
+    pub gravity: Gravity,
+    /// How chains are scored, `Mode::scoring` if unset. Master mode grades by these points.
+    #[serde(default)]
+    pub scoring: Option<Scoring>,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            bombs_limit: 999,
+            level_limit: 999,
+            blast_shape: BlastShape::default(),
+            gravity: Gravity::default(),
+            scoring: None,
+        }
+    }
+}
+
+impl Rules {
+    /// The rule chains are scored by in `mode`.
+    pub fn scoring_rule(&self, mode: Mode) -> Box<dyn ScoringRule> {
+        self.scoring.unwrap_or(mode.scoring()).rule()
+    }
+}
+
+/// An input the game acted on. Animation frames are not recorded; the frame number of each entry
+/// carries the timing instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Action {
+    Feed,
+    Remove { x: usize, y: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub mode: Mode,
+    pub seed: u64,
+    pub rules: Rules,
+    /// Actions with the frame, counted at 60 per second from the start, they happened on.
+    pub actions: Vec<(usize, Action)>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    /// Points scored in normal mode. Master mode has no score and reports zero.
+    pub score: usize,
+    /// Bombs removed in normal mode, the level in master mode.
+    pub level: usize,
+    pub grade: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    UnsupportedVersion(u32),
+    OutOfBounds {
+        x: usize,
+        y: usize,
+    },
+    /// An action is stamped with an earlier frame than the one before it.
+    Unordered {
+        frame: usize,
+    },
+    /// The board is empty or larger than `MAX_BOARD_SIZE` either way.
+    InvalidRules {
+        width: usize,
+        height: usize,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::UnsupportedVersion(version) => {
+                write!(f, "unsupported replay version {version}")
+            }
+            ReplayError::OutOfBounds { x, y } => write!(f, "cell ({x}, {y}) is off the board"),
+            ReplayError::Unordered { frame } => {
+                write!(f, "action at frame {frame} is out of order")
+            }
+            ReplayError::InvalidRules { width, height } => {
+                write!(f, "a {width}x{height} board cannot be played")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl Replay {
+    pub fn new(mode: Mode, seed: u64, rules: Rules) -> Self {
+        Replay {
+            version: REPLAY_VERSION,
+            mode,
+            seed,
+            rules,
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, frame: usize, action: Action) {
+        self.actions.push((frame, action));
+    }
+
+    /// Plays the replay back without rendering and reports how the game ended.
+    pub fn simulate(&self) -> Result<Outcome, ReplayError> {
+        if self.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(self.version));
+        }
+        let (width, height) = (self.rules.width, self.rules.height);
+        if !(1..=MAX_BOARD_SIZE).contains(&width) || !(1..=MAX_BOARD_SIZE).contains(&height) {
+            return Err(ReplayError::InvalidRules { width, height });
+        }
+
+        let mut last_frame = 0;
+        for &(frame, action) in &self.actions {
+            if frame < last_frame {
+                return Err(ReplayError::Unordered { frame });
+            }
+            last_frame = frame;
+            if let Action::Remove { x, y } = action {
+                if x >= self.rules.width || y >= self.rules.height {
+                    return Err(ReplayError::OutOfBounds { x, y });
+                }
+            }
+        }
+
+        let mut state = GameState::new(self.mode, self.seed, self.rules.clone());
+        for &(frame, action) in &self.actions {
+            state.apply(frame, action);
+        }
+        Ok(state.outcome())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn small_rules() -> Rules {
+        Rules {
+            width: 2,
+            height: 3,
+            ..Rules::default()
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut replay = Replay::new(Mode::Normal, 7, small_rules());
+        replay.record(0, Action::Feed);
+        for frame in 1..=3 {
+            replay.record(frame * 30, Action::Remove { x: 0, y: 2 });
+        }
+        replay.record(120, Action::Remove { x: 0, y: 0 });
+
+        let outcome = replay.simulate().unwrap();
+        assert_eq!(
+            outcome,
+            Outcome {
+                score: 9,
+                level: 6,
+                grade: "D".to_string(),
+            }
+        );
+        assert_eq!(replay.simulate().unwrap(), outcome);
+
+        let mut master = replay.clone();
+        master.rules.scoring = Some(Scoring::Master);
+        assert_eq!(master.simulate().unwrap().score, 6);
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let restored: Replay = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, replay);
+    }
+
+    #[test]
+    fn test_master() {
+        let mut replay = Replay::new(Mode::Master, 7, small_rules());
+        replay.record(60, Action::Feed);
+        for frame in [100, 200, 300] {
+            replay.record(frame, Action::Remove { x: 1, y: 2 });
+        }
+
+        let outcome = replay.simulate().unwrap();
+        assert_eq!(outcome.level, 6);
+        assert_eq!(outcome.grade, "C3");
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut replay = Replay::new(Mode::Normal, 0, Rules::default());
+        replay.record(10, Action::Feed);
+        replay.record(5, Action::Feed);
+        assert_eq!(replay.simulate(), Err(ReplayError::Unordered { frame: 5 }));
+
+        replay.actions = vec![(0, Action::Remove { x: 8, y: 0 })];
+        assert_eq!(
+            replay.simulate(),
+            Err(ReplayError::OutOfBounds { x: 8, y: 0 })
+        );
+
+        let mut empty = replay.clone();
+        empty.rules.width = 0;
+        assert_eq!(
+            empty.simulate(),
+            Err(ReplayError::InvalidRules {
+                width: 0,
+                height: DEFAULT_HEIGHT
+            })
+        );
+        empty.rules.width = MAX_BOARD_SIZE + 1;
+        assert!(empty.simulate().is_err());
+
+        replay.version = REPLAY_VERSION + 1;
+        assert_eq!(
+            replay.simulate(),
+            Err(ReplayError::UnsupportedVersion(REPLAY_VERSION + 1))
+        );
+    }
+}
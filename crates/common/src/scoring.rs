@@ -0,0 +1,179 @@
+//! Rules that turn chains into points. Modes pick one through `Scoring`, so the client, the
+//! server and replays score the same game the same way.
+
+use crate::chain::ChainReport;
+use serde::{Deserialize, Serialize};
+
+/// Scores chains one at a time. Rules may keep state between chains, such as a running combo.
+pub trait ScoringRule {
+    /// Points for `report`. `section` is the master mode section, and zero in other modes.
+    fn score(&mut self, report: &ChainReport, section: usize) -> usize;
+
+    /// Forgets any state kept between chains.
+    fn reset(&mut self) {}
+}
+
+/// The n-th of `cells` removed cells is worth n points.
+pub fn triangular(cells: usize) -> usize {
+    (cells + 1) * cells / 2
+}
+
+/// Normal mode scoring.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Triangular;
+
+impl ScoringRule for Triangular {
+    fn score(&mut self, report: &ChainReport, _section: usize) -> usize {
+        triangular(report.removed_count())
+    }
+}
+
+/// Master mode scoring, `sqrt(bombs^3)` scaled up every two sections. Grade offsets are applied
+/// by `GradeManager`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Master;
+
+impl ScoringRule for Master {
+    fn score(&mut self, report: &ChainReport, section: usize) -> usize {
+        fn sqrt(n: usize) -> usize {
+            (0..).take_while(|x| x * x <= n).last().unwrap()
+        }
+
+        let bombs = report.bombs();
+        sqrt(bombs * bombs * bombs) * (section / 2 + 1)
+    }
+}
+
+/// Triangular scoring raised by a percentage for every wave the chain travels.
+#[derive(Clone, Copy, Debug)]
+pub struct ChainDepth {
+    pub percent_per_wave: usize,
+}
+
+impl Default for ChainDepth {
+    fn default() -> Self {
+        ChainDepth {
+            percent_per_wave: 25,
+        }
+    }
+}
+
+impl ScoringRule for ChainDepth {
+    fn score(&mut self, report: &ChainReport, _section: usize) -> usize {
+        triangular(report.removed_count()) * (100 + self.percent_per_wave * report.depth()) / 100
+    }
+}
+
+/// Triangular scoring raised by a percentage for every earlier chain in a row that set off a
+/// bomb. A chain without bombs scores normally and breaks the combo.
+#[derive(Clone, Copy, Debug)]
+pub struct Combo {
+    pub percent_per_combo: usize,
+    combo: usize,
+}
+
+impl Combo {
+    pub fn new(percent_per_combo: usize) -> Self {
+        Combo {
+            percent_per_combo,
+            combo: 0,
+        }
+    }
+
+    pub fn combo(&self) -> usize {
+        self.combo
+    }
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Combo::new(10)
+    }
+}
+
+impl ScoringRule for Combo {
+    fn score(&mut self, report: &ChainReport, _section: usize) -> usize {
+        if report.bombs() == 0 {
+            self.combo = 0;
+            return triangular(report.removed_count());
+        }
+        let score =
+            triangular(report.removed_count()) * (100 + self.percent_per_combo * self.combo) / 100;
+        self.combo += 1;
+        score
+    }
+
+    fn reset(&mut self) {
+        self.combo = 0;
+    }
+}
+
+/// A scoring rule by name, with default parameters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scoring {
+    #[default]
+    Triangular,
+    Master,
+    ChainDepth,
+    Combo,
+}
+
+impl Scoring {
+    pub fn rule(self) -> Box<dyn ScoringRule> {
+        match self {
+            Scoring::Triangular => Box::new(Triangular),
+            Scoring::Master => Box::new(Master),
+            Scoring::ChainDepth => Box::new(ChainDepth::default()),
+            Scoring::Combo => Box::new(Combo::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::{DynamicBoard, Grid};
+
+    fn report(board: &str, x: usize, y: usize) -> ChainReport {
+        board.parse::<DynamicBoard>().unwrap().remove(x, y)
+    }
+
+    #[test]
+    fn test_rules() {
+        // The bomb, then the three cells around it, then the tile the other bombs reach.
+        let chain = report("#*.\n**#", 0, 1);
+        assert_eq!(
+            (chain.removed_count(), chain.bombs(), chain.depth()),
+            (5, 3, 2)
+        );
+        let tile = report("#..\n##.", 0, 1);
+
+        assert_eq!(Triangular.score(&chain, 0), 15);
+        assert_eq!(Master.score(&chain, 0), 5);
+        assert_eq!(Master.score(&chain, 5), 15);
+        assert_eq!(ChainDepth::default().score(&chain, 0), 22);
+        assert_eq!(ChainDepth::default().score(&tile, 0), 1);
+
+        let mut combo = Combo::default();
+        assert_eq!(combo.score(&chain, 0), 15);
+        assert_eq!(combo.score(&chain, 0), 16);
+        assert_eq!(combo.score(&chain, 0), 18);
+        assert_eq!(combo.combo(), 3);
+        assert_eq!(combo.score(&tile, 0), 1);
+        assert_eq!(combo.score(&chain, 0), 15);
+        combo.reset();
+        assert_eq!(combo.combo(), 0);
+
+        for scoring in [
+            Scoring::Triangular,
+            Scoring::Master,
+            Scoring::ChainDepth,
+            Scoring::Combo,
+        ] {
+            assert_eq!(
+                scoring.rule().score(&tile, 0) > 0,
+                scoring != Scoring::Master
+            );
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use crate::board::{CellType, Grid};
+use crate::chain::ChainReport;
+use crate::generator::RowGenerator;
+use crate::scoring::{Scoring, ScoringRule};
+use crate::zobrist::TranspositionTable;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+/// Slots in the table of positions already valued during an expectimax search.
+const TABLE_SIZE: usize = 1 << 16;
+
+/// The depth a position was searched to, with its expected score and survival.
+type Table = TranspositionTable<(usize, f64, f64)>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Ranks moves by their immediate score.
+    Greedy,
+    /// Follows the `width` best lines for `depth` moves, feeding rows drawn from the generator.
+    Beam { width: usize, depth: usize },
+    /// Averages over every row the generator could feed, `depth` moves deep.
+    Expectimax { depth: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Evaluation {
+    pub x: usize,
+    pub y: usize,
+    /// Cells removed by this move alone.
+    pub removed: usize,
+    /// Score expected over the searched moves. Beam search reports the mean score of the move's
+    /// lines at the deepest ply the beam kept any of them.
+    pub expected_score: f64,
+    /// Probability of not topping out over the searched moves. Beam search, which follows
+    /// sampled lines rather than every row, reports the share of the move's lines that are still
+    /// alive at the deepest ply the beam kept any of them.
+    pub survival: f64,
+}
+
+impl Evaluation {
+    fn compare(&self, other: &Evaluation) -> Ordering {
+        other
+            .survival
+            .partial_cmp(&self.survival)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                other
+                    .expected_score
+                    .partial_cmp(&self.expected_score)
+                    .unwrap_or(Ordering::Equal)
+            })
+    }
+}
+
+/// A move that leaves a board different from every other move, with the board it leaves after
+/// gravity has been applied.
+struct Successor<B> {
+    x: usize,
+    y: usize,
+    board: B,
+    report: ChainReport,
+}
+
+fn successors<B: Grid + Clone>(board: &B) -> Vec<Successor<B>> {
+    board
+        .moves()
+        .into_iter()
+        .map(|m| {
+            let mut board = board.clone();
+            let report = board.remove(m.x, m.y);
+            board.apply_gravity();
+            Successor {
+                x: m.x,
+                y: m.y,
+                board,
+                report,
+            }
+        })
+        .collect()
+}
+
+pub struct Solver {
+    pub strategy: Strategy,
+    /// Bombs in every fed row.
+    pub bombs: usize,
+    /// Built once from the solver's `Scoring`. Rules that keep state between chains are scored
+    /// as if every chain were the first.
+    rule: RefCell<Box<dyn ScoringRule>>,
+}
+
+impl Solver {
+    /// Scores chains with `Scoring::Triangular`.
+    pub fn new(strategy: Strategy) -> Self {
+        Solver {
+            strategy,
+            bombs: 2,
+            rule: RefCell::new(Scoring::Triangular.rule()),
+        }
+    }
+
+    pub fn with_scoring(mut self, scoring: Scoring) -> Self {
+        self.rule = RefCell::new(scoring.rule());
+        self
+    }
+
+    fn points(&self, report: &ChainReport) -> f64 {
+        let mut rule = self.rule.borrow_mut();
+        rule.reset();
+        rule.score(report, 0) as f64
+    }
+
+    /// Evaluates every distinct move on `board`, best first. Each move is followed by a feed from
+    /// `generator`, as in the game.
+    pub fn rank<B, G>(&self, board: &B, generator: &G) -> Vec<Evaluation>
+    where
+        B: Grid + Clone,
+        G: RowGenerator + Clone,
+    {
+        let mut evaluations = match self.strategy {
+            Strategy::Greedy => {
+                let mut evaluations = self.greedy(board);
+                evaluations.sort_by(|a, b| {
+                    b.expected_score
+                        .partial_cmp(&a.expected_score)
+                        .unwrap_or(Ordering::Equal)
+                });
+                return evaluations;
+            }
+            Strategy::Beam { width, depth } => self.beam(board, generator, width, depth),
+            Strategy::Expectimax { depth } => self.expectimax(board, generator, depth),
+        };
+        evaluations.sort_by(Evaluation::compare);
+        evaluations
+    }
+
+    pub fn best_move<B, G>(&self, board: &B, generator: &G) -> Option<Evaluation>
+    where
+        B: Grid + Clone,
+        G: RowGenerator + Clone,
+    {
+        self.rank(board, generator).into_iter().next()
+    }
+
+    /// Scores each move on its own, without feeding a row after it.
+    fn greedy<B: Grid + Clone>(&self, board: &B) -> Vec<Evaluation> {
+        successors(board)
+            .into_iter()
+            .map(|successor| Evaluation {
+                x: successor.x,
+                y: successor.y,
+                removed: successor.report.removed_count(),
+                expected_score: self.points(&successor.report),
+                survival: if successor.board.is_filled() { 0. } else { 1. },
+            })
+            .collect()
+    }
+
+    /// Deeper plies reuse the distribution of the next row rather than tracking how each fed row
+    /// changes the generator.
+    fn expectimax<B, G>(&self, board: &B, generator: &G, depth: usize) -> Vec<Evaluation>
+    where
+        B: Grid + Clone,
+        G: RowGenerator + Clone,
+    {
+        // A move always looks at the row fed after it, as `beam` does.
+        let depth = depth.max(1);
+        let outcomes = generator.outcomes(self.bombs);
+        let mut table = Table::new(TABLE_SIZE);
+        successors(board)
+            .into_iter()
+            .map(|successor| {
+                let (expected_score, survival) =
+                    self.expect(&successor, &outcomes, depth, &mut table);
+                Evaluation {
+                    x: successor.x,
+                    y: successor.y,
+                    removed: successor.report.removed_count(),
+                    expected_score,
+                    survival,
+                }
+            })
+            .collect()
+    }
+
+    /// Expected score and survival of a move, averaged over the rows that may be fed after it.
+    fn expect<B: Grid + Clone>(
+        &self,
+        successor: &Successor<B>,
+        outcomes: &[(f64, Vec<CellType>)],
+        depth: usize,
+        table: &mut Table,
+    ) -> (f64, f64) {
+        let score = self.points(&successor.report);
+        if successor.board.is_filled() {
+            return (score, 0.);
+        }
+
+        outcomes.iter().fold(
+            (score, 0.),
+            |(expected_score, survival), (probability, row)| {
+                let mut board = successor.board.clone();
+                board.feed(row);
+                let (score, alive) = self.value(&board, outcomes, depth - 1, table);
+                (
+                    expected_score + probability * score,
+                    survival + probability * alive,
+                )
+            },
+        )
+    }
+
+    /// Expected score and survival of the best move on `board`. Positions reached again through
+    /// another line are looked up in `table`.
+    fn value<B: Grid + Clone>(
+        &self,
+        board: &B,
+        outcomes: &[(f64, Vec<CellType>)],
+        depth: usize,
+        table: &mut Table,
+    ) -> (f64, f64) {
+        if depth == 0 {
+            return (0., 1.);
+        }
+
+        let hash = board.position_hash();
+        if let Some(&(searched, score, survival)) = table.get(hash) {
+            if searched == depth {
+                return (score, survival);
+            }
+        }
+
+        let (score, survival) = successors(board)
+            .iter()
+            .map(|successor| self.expect(successor, outcomes, depth, table))
+            .max_by(|a, b| {
+                a.1.partial_cmp(&b.1)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal))
+            })
+            .unwrap_or((0., 1.));
+        table.insert(hash, (depth, score, survival));
+        (score, survival)
+    }
+
+    /// Rows come from clones of `generator`, so pass a reseeded generator to avoid searching with
+    /// knowledge of the real upcoming rows.
+    fn beam<B, G>(&self, board: &B, generator: &G, width: usize, depth: usize) -> Vec<Evaluation>
+    where
+        B: Grid + Clone,
+        G: RowGenerator + Clone,
+    {
+        struct Line<B, G> {
+            first: usize,
+            board: B,
+            generator: G,
+            score: f64,
+        }
+
+        let first_moves = successors(board);
+        let mut evaluations: Vec<_> = first_moves
+            .iter()
+            .map(|successor| Evaluation {
+                x: successor.x,
+                y: successor.y,
+                removed: successor.report.removed_count(),
+                expected_score: 0.,
+                survival: 0.,
+            })
+            .collect();
+
+        let mut lines: Vec<_> = first_moves
+            .into_iter()
+            .enumerate()
+            .map(|(first, successor)| Line {
+                first,
+                board: successor.board,
+                generator: generator.clone(),
+                score: self.points(&successor.report),
+            })
+            .collect();
+
+        for ply in 0..depth.max(1) {
+            // Lines, survivors and total score of each first move at this ply.
+            let mut counts = vec![(0, 0, 0.); evaluations.len()];
+            let mut survivors = Vec::new();
+            for mut line in lines {
+                counts[line.first].0 += 1;
+                counts[line.first].2 += line.score;
+                if line.board.is_filled() {
+                    continue;
+                }
+                let row = line.generator.next_row(self.bombs);
+                line.board.feed(&row);
+                counts[line.first].1 += 1;
+                survivors.push(line);
+            }
+            for (evaluation, &(lines, alive, score)) in evaluations.iter_mut().zip(&counts) {
+                if lines > 0 {
+                    evaluation.expected_score = score / lines as f64;
+                    evaluation.survival = alive as f64 / lines as f64;
+                }
+            }
+
+            if ply + 1 == depth.max(1) {
+                break;
+            }
+
+            let mut children: Vec<_> = survivors
+                .into_iter()
+                .flat_map(|line| {
+                    let children: Vec<_> = successors(&line.board)
+                        .into_iter()
+                        .map(|successor| Line {
+                            first: line.first,
+                            score: line.score + self.points(&successor.report),
+                            board: successor.board,
+                            generator: line.generator.clone(),
+                        })
+                        .collect();
+                    if children.is_empty() {
+                        vec![line]
+                    } else {
+                        children
+                    }
+                })
+                .collect();
+            children.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+            // Lines from the same first move that reach the same position share their future, so
+            // only the best scoring one is kept.
+            let mut seen = HashSet::new();
+            children.retain(|line| seen.insert((line.first, line.board.position_hash())));
+            children.truncate(width);
+            lines = children;
+        }
+
+        evaluations
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::board::DynamicBoard;
+    use crate::generator::{BagGenerator, SpreadGenerator};
+
+    #[test]
+    fn test_successors() {
+        let board: DynamicBoard = "
+            ....
+            #*#.
+            **##
+        "
+        .parse()
+        .unwrap();
+        let moves: Vec<_> = successors(&board)
+            .iter()
+            .map(|x| (x.x, x.y, x.report.removed_count()))
+            .collect();
+        assert_eq!(
+            moves,
+            vec![(0, 1, 1), (0, 2, 6), (2, 1, 1), (2, 2, 1), (3, 2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_greedy() {
+        let board: DynamicBoard = "
+            ....
+            #...
+            #.*#
+            ##*#
+        "
+        .parse()
+        .unwrap();
+        let solver = Solver::new(Strategy::Greedy);
+        let best = solver.best_move(&board, &BagGenerator::new(4, 0)).unwrap();
+        assert_eq!((best.x, best.y, best.removed), (2, 2, 5));
+        assert_eq!(best.expected_score, 15.);
+        assert!((best.survival - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_expectimax_survival() {
+        let board: DynamicBoard = "
+            #.#
+            #*#
+            ###
+        "
+        .parse()
+        .unwrap();
+        let solver = Solver::new(Strategy::Expectimax { depth: 1 });
+        let ranked = solver.rank(&board, &SpreadGenerator::new(3, 0));
+        assert_eq!(ranked.len(), 8);
+        assert!((ranked[0].survival - 1.).abs() < 1e-9);
+        assert_eq!((ranked[0].x, ranked[0].y, ranked[0].removed), (1, 1, 8));
+        assert!(ranked[1..].iter().all(|x| x.survival == 0.));
+
+        let solver = Solver::new(Strategy::Expectimax { depth: 2 });
+        let best = solver
+            .best_move(&board, &SpreadGenerator::new(3, 0))
+            .unwrap();
+        assert_eq!((best.x, best.y), (1, 1));
+        assert!(best.expected_score > 36.);
+
+        let solver = Solver::new(Strategy::Expectimax { depth: 0 });
+        assert_eq!(solver.rank(&board, &SpreadGenerator::new(3, 0)), ranked);
+    }
+
+    #[test]
+    fn test_beam() {
+        let mut board = DynamicBoard::new(6, 8);
+        let mut generator = SpreadGenerator::new(6, 9);
+        for _ in 0..4 {
+            board.feed(&generator.next_row(2));
+        }
+
+        let solver = Solver::new(Strategy::Beam { width: 8, depth: 3 });
+        let ranked = solver.rank(&board, &generator);
+        assert_eq!(ranked, solver.rank(&board, &generator));
+        assert_eq!(ranked[0].survival, 1.);
+        assert!(ranked.iter().all(|x| (0. ..=1.).contains(&x.survival)));
+        assert!(ranked[0].expected_score >= ranked[ranked.len() - 1].expected_score);
+        let greedy = Solver::new(Strategy::Greedy).rank(&board, &generator);
+        assert!(ranked[0].expected_score >= greedy[0].expected_score);
+    }
+}
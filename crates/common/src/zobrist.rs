@@ -0,0 +1,160 @@
+//! Zobrist hashing of board layouts. A position hash is the XOR of one key per occupied cell, so
+//! it ignores cell ids and can be updated cell by cell as the board changes.
+
+use crate::board::CellType;
+use crate::rng::Rng;
+
+/// The key of a `cell_type` cell at `(x, y)`. Keys are derived from the coordinates instead of
+/// read from a table, so boards of any size hash the same way on every target.
+pub fn key(x: usize, y: usize, cell_type: CellType) -> u64 {
+    let index = ((x as u64) << 32 | y as u64) * CellType::ALL.len() as u64 + cell_type as u64;
+    Rng::new(index).next_u64()
+}
+
+/// A fixed number of slots, each holding the value last stored for a hash that maps to it. Newer
+/// entries replace older ones, so memory stays bounded however long a search runs.
+#[derive(Clone, Debug)]
+pub struct TranspositionTable<V> {
+    slots: Vec<Option<(u64, V)>>,
+    len: usize,
+    hits: usize,
+    misses: usize,
+}
+
+impl<V> TranspositionTable<V> {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0);
+        TranspositionTable {
+            slots: (0..capacity).map(|_| None).collect(),
+            len: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn slot(&self, hash: u64) -> usize {
+        (hash % self.slots.len() as u64) as usize
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Lookups that found a stored position, and lookups that did not.
+    pub fn stats(&self) -> (usize, usize) {
+        (self.hits, self.misses)
+    }
+
+    pub fn get(&mut self, hash: u64) -> Option<&V> {
+        let slot = self.slot(hash);
+        match &self.slots[slot] {
+            Some((stored, value)) if *stored == hash => {
+                self.hits += 1;
+                Some(value)
+            }
+            _ => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn contains(&self, hash: u64) -> bool {
+        matches!(&self.slots[self.slot(hash)], Some((stored, _)) if *stored == hash)
+    }
+
+    /// Stores `value` for `hash`, returning the entry it evicted, if any.
+    pub fn insert(&mut self, hash: u64, value: V) -> Option<(u64, V)> {
+        let slot = self.slot(hash);
+        let evicted = self.slots[slot].replace((hash, value));
+        if evicted.is_none() {
+            self.len += 1;
+        }
+        evicted
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+        self.hits = 0;
+        self.misses = 0;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bitboard::BitBoard;
+    use crate::board::{DynamicBoard, Grid};
+
+    #[test]
+    fn test_position_hash() {
+        let board: DynamicBoard = "
+            ...
+            #*.
+            @#+
+        "
+        .parse()
+        .unwrap();
+        let same: DynamicBoard = "
+            ...
+            #*.
+            @#+
+        "
+        .parse()
+        .unwrap();
+        let mut renumbered = DynamicBoard::new(3, 3);
+        renumbered.feed(&[CellType::Tile; 3]);
+        renumbered.feed(&[CellType::Armored, CellType::Tile, CellType::CrossBomb]);
+        renumbered.column_mut(1)[1] = Some(renumbered.generate_cell(CellType::Bomb));
+        renumbered.column_mut(2)[1] = None;
+
+        assert_eq!(board.position_hash(), same.position_hash());
+        assert_eq!(board.position_hash(), renumbered.position_hash());
+        assert_eq!(
+            board.position_hash(),
+            BitBoard::from(&board).position_hash()
+        );
+        assert_ne!(
+            DynamicBoard::new(3, 3).position_hash(),
+            board.position_hash()
+        );
+
+        let mut moved = board.clone();
+        moved.remove(0, 1);
+        assert_eq!(
+            moved.position_hash(),
+            board.position_hash() ^ key(0, 1, CellType::Tile)
+        );
+        assert_ne!(
+            key(0, 1, CellType::Tile),
+            key(1, 0, CellType::Tile),
+            "coordinates are not symmetric"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let mut table = TranspositionTable::new(4);
+        assert!(table.insert(1, "a").is_none());
+        assert!(table.insert(2, "b").is_none());
+        assert_eq!(table.get(1), Some(&"a"));
+        assert_eq!(table.get(3), None);
+        assert_eq!(table.insert(5, "c"), Some((1, "a")));
+        assert_eq!(table.get(1), None);
+        assert!(table.contains(5));
+        assert_eq!((table.len(), table.stats()), (2, (1, 2)));
+
+        table.clear();
+        assert!(table.is_empty());
+        assert_eq!(table.capacity(), 4);
+    }
+}
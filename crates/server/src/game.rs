@@ -1,144 +1,266 @@
+use crate::room::Room;
+use crate::session::{Sessions, RECONNECT_GRACE};
+use crate::standings::Standings;
 use actix::prelude::*;
 use actix_web_actors::ws;
-use common::board::{Board, CellType};
-use common::model::{RequestMessage, ResponseMessage};
+use common::board::{CellType, DynamicBoard, Grid, DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use common::chain::ChainReport;
+use common::garbage::{Attack, GarbageQueue, Opponent, Targeting, MAX_ROWS_PER_TURN};
+use common::generator::{RowGenerator, SpreadGenerator};
+use common::model::{BoardSnapshot, GameInfo, RequestMessage, ResponseMessage};
+use common::replay::{Mode, Rules};
+use common::rng::Rng;
+use common::scoring::ScoringRule;
 use rand::prelude::*;
 use std::collections::{HashMap, HashSet};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-const WIDTH: usize = 8;
-const HEIGHT: usize = 9;
+const WIDTH: usize = DEFAULT_WIDTH;
+const HEIGHT: usize = DEFAULT_HEIGHT;
+const MAX_PLAYERS: usize = 8;
+const ROOM_CODE_LENGTH: usize = 4;
+/// Letters and digits that cannot be mistaken for each other when read out.
+const ROOM_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
 
-fn cumulate(iter: impl Iterator<Item = usize>) -> impl Iterator<Item = usize> {
-    iter.scan(0, |acc, x| {
-        *acc += x;
-        Some(*acc)
-    })
-}
-
-#[derive(Clone)]
-struct BombGenerator {
-    rng: ThreadRng,
-    generated: [usize; WIDTH],
+struct BoardManager {
+    id: usize,
+    board: DynamicBoard,
+    generator: SpreadGenerator,
+    scoring: Box<dyn ScoringRule>,
+    score: usize,
+    garbage: GarbageQueue,
+    rng: Rng,
+    targeting: Targeting,
+    /// The player this board's last attack went to.
+    target: Option<usize>,
 }
 
-impl BombGenerator {
-    fn new() -> Self {
-        let rng = thread_rng();
-        BombGenerator {
-            rng,
-            generated: [0; WIDTH],
+impl BoardManager {
+    fn new(id: usize, rules: &Rules) -> Self {
+        BoardManager {
+            id,
+            board: DynamicBoard::new(WIDTH, HEIGHT),
+            generator: SpreadGenerator::new(WIDTH, random()),
+            scoring: rules.scoring_rule(Mode::Normal),
+            score: 0,
+            garbage: GarbageQueue::new(),
+            rng: Rng::new(random()),
+            targeting: Targeting::default(),
+            target: None,
         }
     }
 
-    fn possibility(&self) -> Vec<usize> {
-        let max = self.generated.iter().copied().max().unwrap();
-        self.generated
-            .iter()
-            .map(|x| 1 << ((max - x) * 2))
-            .collect()
+    /// Topped out, the same way `AnimatedBoard::is_filled` tells on the client.
+    fn is_filled(&self) -> bool {
+        self.board.is_filled()
     }
 
-    fn next_double(&mut self) -> (usize, usize) {
-        let mut possibility = self.possibility();
+    fn stack(&self) -> usize {
+        self.board.column_heights().into_iter().max().unwrap_or(0)
+    }
 
-        let mut sum = possibility.iter().sum::<usize>();
-        let r = self.rng.gen_range(0..sum);
+    fn remove(&mut self, x: usize, y: usize) -> ChainReport {
+        let report = self.board.remove(x, y);
+        self.score += self.scoring.score(&report, 0);
+        self.board.apply_gravity();
+        report
+    }
 
-        let left = cumulate(possibility.iter().copied())
-            .enumerate()
-            .filter(|&(_, x)| r < x)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap();
+    fn feed(&mut self) -> Vec<CellType> {
+        let row = self.generator.next_row(2);
+        self.board.feed(&row);
+        row
+    }
 
-        sum -= possibility[left];
-        possibility[left] = 0;
-        let r = self.rng.gen_range(0..sum);
+    /// Feeds up to a turn's worth of pending garbage, returning the rows fed.
+    fn feed_garbage(&mut self) -> Vec<Vec<CellType>> {
+        let rows = self
+            .garbage
+            .take(MAX_ROWS_PER_TURN)
+            .to_rows(WIDTH, &mut self.rng);
+        for row in &rows {
+            self.board.feed(row);
+        }
+        rows
+    }
+}
 
-        let right = cumulate(possibility.iter().copied())
-            .enumerate()
-            .filter(|&(_, x)| r < x)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap();
+struct Game {
+    participants: HashMap<Addr<Player>, BoardManager>,
+    /// Boards of players who quit, kept to show where they finished.
+    departed: Vec<BoardManager>,
+    /// How many players the match started with.
+    players: usize,
+    standings: Standings,
+    sessions: Sessions,
+    /// Watch every board without playing.
+    spectators: HashSet<Addr<Player>>,
+    rng: Rng,
+    /// Ends the match in a draw between the players still in once it has gone on this long.
+    time_limit: Option<Duration>,
+    matchmaker: Addr<Matchmaker>,
+}
 
-        self.generated[left] += 1;
-        self.generated[right] += 1;
-        (left, right)
+impl Game {
+    /// Numbers the players in the order given. Each comes with their session token.
+    fn new(
+        participants: Vec<(Addr<Player>, String)>,
+        rules: &Rules,
+        time_limit: Option<Duration>,
+        matchmaker: Addr<Matchmaker>,
+    ) -> Self {
+        let players = participants.len();
+        let (participants, sessions): (Vec<_>, _) = participants.into_iter().unzip();
+        let participants = participants
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| (x, BoardManager::new(id, rules)))
+            .collect();
+        Game {
+            participants,
+            departed: Vec::new(),
+            players,
+            standings: Standings::new(players),
+            sessions: Sessions::new(sessions),
+            spectators: HashSet::new(),
+            rng: Rng::new(random()),
+            time_limit,
+            matchmaker,
+        }
     }
 
-    fn next_single(&mut self) -> usize {
-        let possibility = self.possibility();
+    /// Every board as it is now, in player order.
+    fn snapshot(&self) -> Vec<BoardSnapshot> {
+        let mut boards: Vec<_> = self
+            .participants
+            .values()
+            .chain(&self.departed)
+            .map(|board| BoardSnapshot {
+                player: board.id,
+                board: board.board.clone(),
+                place: self.standings.place(board.id),
+            })
+            .collect();
+        boards.sort_by_key(|board| board.player);
+        boards
+    }
 
-        let sum = possibility.iter().sum::<usize>();
-        let r = self.rng.gen_range(0..sum);
+    /// Sends to the players and the spectators.
+    fn broadcast(&self, message: ResponseMessage) {
+        for player in self.participants.keys().chain(&self.spectators) {
+            player.do_send(Response(message.clone()));
+        }
+    }
 
-        let bomb = cumulate(possibility.iter().copied())
-            .enumerate()
-            .filter(|&(_, x)| r < x)
-            .map(|(i, _)| i)
-            .next()
-            .unwrap();
+    /// Sends to everyone but the player, spectators included.
+    fn send_opponents(&self, player: &Addr<Player>, message: ResponseMessage) {
+        for opponent in self.participants.keys().chain(&self.spectators) {
+            if opponent != player {
+                opponent.do_send(Response(message.clone()));
+            }
+        }
+    }
 
-        self.generated[bomb] += 1;
-        bomb
+    /// Shows everyone else how high the player's columns are.
+    fn send_stack(&self, player: &Addr<Player>) {
+        let board = &self.participants[player];
+        let message = ResponseMessage::Stack {
+            player: board.id,
+            heights: board.board.column_heights(),
+        };
+        self.send_opponents(player, message);
     }
-}
 
-struct BoardManager {
-    board: Board<WIDTH, HEIGHT>,
-    generator: BombGenerator,
-}
+    /// The connection playing the board of player `id`.
+    fn player(&self, id: usize) -> Option<Addr<Player>> {
+        self.participants
+            .iter()
+            .find(|(_, board)| board.id == id)
+            .map(|(player, _)| player.clone())
+    }
 
-impl BoardManager {
-    fn new() -> Self {
-        BoardManager {
-            board: Board::new(),
-            generator: BombGenerator::new(),
+    /// Eliminates the player if their board topped out, placing them behind everyone still in.
+    fn check_top_out(&mut self, player: &Addr<Player>, ctx: &mut Context<Self>) {
+        let board = &self.participants[player];
+        if !board.is_filled() {
+            return;
         }
+        let id = board.id;
+        let Some(place) = self.standings.eliminate(id) else {
+            return;
+        };
+        self.broadcast(ResponseMessage::Placement { player: id, place });
+        player.do_send(Response(ResponseMessage::Lose));
+        self.check_end(ctx);
     }
 
-    fn remove(&mut self, x: usize, y: usize) -> usize {
-        let removed_cells = self.board.remove(x, y).len();
-        self.board.apply_gravity();
-        removed_cells
-    }
-
-    fn feed(&mut self, single: bool) -> [CellType; WIDTH] {
-        let row = if single {
-            let bomb = self.generator.next_single();
-            let mut row = [CellType::Tile; WIDTH];
-            row[bomb] = CellType::Bomb;
-            row
-        } else {
-            let bombs = self.generator.next_double();
-            let mut row = [CellType::Tile; WIDTH];
-            row[bombs.0] = CellType::Bomb;
-            row[bombs.1] = CellType::Bomb;
-            row
-        };
-        self.board.feed(&row);
-        row
+    /// Ends the match once at most one player is still in, who wins it.
+    fn check_end(&mut self, ctx: &mut Context<Self>) {
+        if !self.standings.is_over() {
+            return;
+        }
+        if let Some(id) = self.standings.crown() {
+            self.broadcast(ResponseMessage::Placement {
+                player: id,
+                place: 1,
+            });
+            if let Some(player) = self.player(id) {
+                player.do_send(Response(ResponseMessage::Win));
+            }
+        }
+        self.end(ctx);
     }
-}
 
-struct Game {
-    participants: HashMap<Addr<Player>, BoardManager>,
-}
+    fn end(&mut self, ctx: &mut Context<Self>) {
+        self.broadcast(ResponseMessage::GameOver);
+        for player in self.participants.keys().chain(&self.spectators) {
+            player.do_send(LeaveGame(ctx.address()));
+        }
+        ctx.stop();
+    }
 
-impl Game {
-    fn new(participants: Vec<Addr<Player>>) -> Self {
-        let participants = participants
-            .into_iter()
-            .map(|x| (x, BoardManager::new()))
+    /// Picks who the player's attack goes to among the opponents still in, by the player's
+    /// targeting strategy.
+    fn choose_target(&mut self, player: &Addr<Player>) -> Option<Addr<Player>> {
+        let board = &self.participants[player];
+        let (id, targeting) = (board.id, board.targeting);
+        let candidates: Vec<_> = self
+            .participants
+            .iter()
+            .filter(|(opponent, board)| *opponent != player && self.standings.is_in(board.id))
             .collect();
-        Game { participants }
+        let opponents: Vec<_> = candidates
+            .iter()
+            .map(|(_, board)| Opponent {
+                stack: board.stack(),
+                attacking: board.target == Some(id),
+            })
+            .collect();
+        let index = targeting.choose(&opponents, &mut self.rng)?;
+        Some(candidates[index].0.clone())
     }
 }
 
 impl Actor for Game {
     type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let Some(time_limit) = self.time_limit else {
+            return;
+        };
+        ctx.run_later(time_limit, |game, ctx| {
+            for (player, board) in &game.participants {
+                if game.standings.is_in(board.id) {
+                    player.do_send(Response(ResponseMessage::Draw));
+                }
+            }
+            game.end(ctx);
+        });
+    }
+
+    fn stopped(&mut self, ctx: &mut Self::Context) {
+        self.matchmaker.do_send(EndGame(ctx.address()));
+    }
 }
 
 #[derive(Message)]
@@ -152,19 +274,54 @@ struct Remove {
 impl Handler<Remove> for Game {
     type Result = ();
 
-    fn handle(&mut self, msg: Remove, _ctx: &mut Self::Context) {
+    fn handle(&mut self, msg: Remove, ctx: &mut Self::Context) {
         let Remove { player, x, y } = msg;
-        let board = if let Some(board) = self.participants.get_mut(&player) {
-            board
-        } else {
-            return;
+        let board = match self.participants.get_mut(&player) {
+            Some(board) if self.standings.is_in(board.id) => board,
+            _ => return,
         };
-        let removed_cells = board.remove(x, y);
-        if removed_cells > 0 {
-            player.do_send(Response(ResponseMessage::Remove { x, y }));
-            let row = board.feed(false).map(|x| x == CellType::Bomb);
-            player.do_send(Response(ResponseMessage::Feed { row: row.to_vec() }));
+        let report = board.remove(x, y);
+        if report.is_empty() {
+            return;
+        }
+
+        player.do_send(Response(ResponseMessage::Score { score: board.score }));
+        let attack = board.garbage.offset(Attack::from_report(&report));
+        let mut rows = vec![board.feed()];
+        rows.extend(board.feed_garbage());
+        let (id, pending) = (board.id, board.garbage.pending());
+
+        player.do_send(Response(ResponseMessage::Remove { x, y }));
+        self.send_opponents(
+            &player,
+            ResponseMessage::OpponentRemove { player: id, x, y },
+        );
+        for row in rows {
+            player.do_send(Response(ResponseMessage::Feed { row: row.clone() }));
+            self.send_opponents(&player, ResponseMessage::OpponentFeed { player: id, row });
         }
+        player.do_send(Response(ResponseMessage::Garbage { pending }));
+        self.send_stack(&player);
+        self.check_top_out(&player, ctx);
+
+        // A player who just topped out attacks no one, and the match may already be over.
+        if attack.is_empty() || !self.standings.is_in(id) {
+            return;
+        }
+        let Some(target) = self.choose_target(&player) else {
+            return;
+        };
+        let target_board = self.participants.get_mut(&target).unwrap();
+        target_board.garbage.receive(attack);
+        target.do_send(Response(ResponseMessage::Garbage {
+            pending: target_board.garbage.pending(),
+        }));
+        let target_id = target_board.id;
+        self.participants.get_mut(&player).unwrap().target = Some(target_id);
+        player.do_send(Response(ResponseMessage::Sent {
+            attack,
+            target: target_id,
+        }));
     }
 }
 
@@ -178,22 +335,250 @@ impl Handler<Feed> for Game {
     fn handle(&mut self, msg: Feed, _ctx: &mut Self::Context) {
         let Feed(player) = msg;
         if let Some(board) = self.participants.get_mut(&player) {
-            let row = board.feed(false).map(|x| x == CellType::Bomb);
-            player.do_send(Response(ResponseMessage::Feed { row: row.to_vec() }));
+            let row = board.feed();
+            let id = board.id;
+            player.do_send(Response(ResponseMessage::Feed { row: row.clone() }));
+            self.send_opponents(&player, ResponseMessage::OpponentFeed { player: id, row });
+            self.send_stack(&player);
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Target {
+    player: Addr<Player>,
+    targeting: Targeting,
+}
+
+impl Handler<Target> for Game {
+    type Result = ();
+
+    fn handle(&mut self, msg: Target, _ctx: &mut Self::Context) {
+        let Target { player, targeting } = msg;
+        if let Some(board) = self.participants.get_mut(&player) {
+            board.targeting = targeting;
+        }
+    }
+}
+
+/// A player left the match, on purpose or by disconnecting. If they were still in, they are
+/// placed as if they had topped out.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Quit(Addr<Player>);
+
+impl Handler<Quit> for Game {
+    type Result = ();
+
+    fn handle(&mut self, Quit(player): Quit, ctx: &mut Self::Context) {
+        let Some(board) = self.participants.remove(&player) else {
+            return;
+        };
+        self.sessions.forget(board.id);
+        let place = self.standings.eliminate(board.id);
+        if let Some(place) = place {
+            self.broadcast(ResponseMessage::Placement {
+                player: board.id,
+                place,
+            });
+        }
+        self.departed.push(board);
+        if place.is_some() {
+            self.check_end(ctx);
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Subscribe(Addr<Player>);
+
+impl Handler<Subscribe> for Game {
+    type Result = ();
+
+    fn handle(&mut self, Subscribe(spectator): Subscribe, ctx: &mut Self::Context) {
+        spectator.do_send(Watch(ctx.address()));
+        spectator.do_send(Response(ResponseMessage::Snapshot {
+            boards: self.snapshot(),
+        }));
+        self.spectators.insert(spectator);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Unsubscribe(Addr<Player>);
+
+impl Handler<Unsubscribe> for Game {
+    type Result = ();
+
+    fn handle(&mut self, Unsubscribe(spectator): Unsubscribe, _ctx: &mut Self::Context) {
+        self.spectators.remove(&spectator);
+    }
+}
+
+/// A player lost their connection. Their board waits for them to resume their session, and
+/// they are eliminated if they do not.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Disconnect(Addr<Player>);
+
+impl Handler<Disconnect> for Game {
+    type Result = ();
+
+    fn handle(&mut self, Disconnect(player): Disconnect, ctx: &mut Self::Context) {
+        let Some(board) = self.participants.get(&player) else {
+            return;
+        };
+        self.sessions.disconnect(board.id, Instant::now());
+        ctx.run_later(RECONNECT_GRACE, |game, ctx| {
+            for id in game.sessions.expired(Instant::now()) {
+                if game.standings.is_over() {
+                    break;
+                }
+                if let Some(player) = game.player(id) {
+                    Handler::<Quit>::handle(game, Quit(player), ctx);
+                }
+            }
+        });
+    }
+}
+
+/// A player reconnected with the session token of a board in this match.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Reattach {
+    player: Addr<Player>,
+    session: String,
+}
+
+impl Handler<Reattach> for Game {
+    type Result = ();
+
+    fn handle(&mut self, msg: Reattach, ctx: &mut Self::Context) {
+        let Reattach { player, session } = msg;
+        let previous = self
+            .sessions
+            .resume(&session, Instant::now())
+            .and_then(|id| self.player(id));
+        let Some(previous) = previous else {
+            player.do_send(Response(ResponseMessage::SessionExpired));
+            return;
+        };
+
+        // A connection that is somehow still open loses the board to the new one.
+        previous.do_send(LeaveGame(ctx.address()));
+        let board = self.participants.remove(&previous).unwrap();
+        let (id, score, pending) = (board.id, board.score, board.garbage.pending());
+        self.participants.insert(player.clone(), board);
+
+        player.do_send(Rejoin(ctx.address()));
+        player.do_send(Response(ResponseMessage::Resumed {
+            player: id,
+            players: self.players,
+            boards: self.snapshot(),
+            score,
+            pending,
+        }));
+    }
+}
+
+impl Room<Addr<Player>> {
+    /// Tells everyone in the room how many are in it and ready.
+    fn broadcast(&self, code: &str) {
+        for player in self.players() {
+            player.do_send(Response(ResponseMessage::Room {
+                code: code.to_string(),
+                players: self.len(),
+                ready: self.ready(),
+            }));
         }
     }
 }
 
 pub struct Matchmaker {
     waiting_players: HashSet<Addr<Player>>,
-    games: Vec<Addr<Game>>,
+    rooms: HashMap<String, Room<Addr<Player>>>,
+    games: Vec<(GameInfo, Addr<Game>)>,
+    next_game_id: usize,
+    /// The match each session token belongs to.
+    sessions: HashMap<String, Addr<Game>>,
+    /// What every match is played by.
+    rules: Rules,
+    /// How long a match may go on before it is a draw, if it is limited at all.
+    time_limit: Option<Duration>,
 }
 
 impl Matchmaker {
-    pub fn new() -> Self {
+    pub fn new(rules: Rules, time_limit: Option<Duration>) -> Self {
         Matchmaker {
             waiting_players: HashSet::new(),
+            rooms: HashMap::new(),
             games: Vec::new(),
+            next_game_id: 0,
+            sessions: HashMap::new(),
+            rules,
+            time_limit,
+        }
+    }
+
+    fn start_game(&mut self, players: Vec<Addr<Player>>, ctx: &mut Context<Self>) {
+        let sessions: Vec<_> = players
+            .iter()
+            .map(|_| format!("{:032x}", random::<u128>()))
+            .collect();
+        let participants = players.iter().cloned().zip(sessions.clone()).collect();
+        let game = Game::new(participants, &self.rules, self.time_limit, ctx.address()).start();
+        for (id, (player, session)) in players.iter().zip(sessions).enumerate() {
+            player.do_send(JoinGame {
+                game: game.clone(),
+                player: id,
+                players: players.len(),
+                width: self.rules.width,
+                height: self.rules.height,
+                session: session.clone(),
+            });
+            self.sessions.insert(session, game.clone());
+        }
+        let info = GameInfo {
+            id: self.next_game_id,
+            players: players.len(),
+        };
+        self.next_game_id += 1;
+        for player in players {
+            game.do_send(Feed(player));
+        }
+        self.games.push((info, game));
+    }
+
+    fn new_code(&self) -> String {
+        let mut rng = Rng::new(random());
+        loop {
+            let code: String = (0..ROOM_CODE_LENGTH)
+                .map(|_| ROOM_CODE_CHARS[rng.below(ROOM_CODE_CHARS.len() as u64) as usize] as char)
+                .collect();
+            if !self.rooms.contains_key(&code) {
+                return code;
+            }
+        }
+    }
+
+    /// Takes the player out of the queue and any room, closing the room if it empties.
+    fn withdraw(&mut self, player: &Addr<Player>) {
+        self.waiting_players.remove(player);
+        let code = self
+            .rooms
+            .iter()
+            .find_map(|(code, room)| room.contains(player).then(|| code.clone()));
+        if let Some(code) = code {
+            let room = self.rooms.get_mut(&code).unwrap();
+            room.leave(player);
+            if room.is_empty() {
+                self.rooms.remove(&code);
+            } else {
+                room.broadcast(&code);
+            }
         }
     }
 }
@@ -202,29 +587,21 @@ impl Actor for Matchmaker {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        ctx.run_interval(Duration::from_secs(5), |matchmaker, _ctx| {
-            let pairs: Vec<_> = matchmaker
-                .waiting_players
-                .iter()
-                .scan::<Option<&Addr<Player>>, _, _>(None, |acc, x| {
-                    if let Some(left) = acc.take() {
-                        Some(Some((left.clone(), x.clone())))
-                    } else {
-                        *acc = Some(x);
-                        Some(None)
-                    }
-                })
-                .flatten()
-                .collect();
-            for (left, right) in pairs {
-                matchmaker.waiting_players.remove(&left);
-                matchmaker.waiting_players.remove(&right);
-                let game = Game::new(vec![left.clone(), right.clone()]).start();
-                left.do_send(JoinGame(game.clone()));
-                right.do_send(JoinGame(game.clone()));
-                game.do_send(Feed(left));
-                game.do_send(Feed(right));
-                matchmaker.games.push(game);
+        // Everyone waiting is split into matches of similar sizes, as few as `MAX_PLAYERS` allows.
+        ctx.run_interval(Duration::from_secs(5), |matchmaker, ctx| {
+            let waiting: Vec<_> = matchmaker.waiting_players.iter().cloned().collect();
+            if waiting.len() < 2 {
+                return;
+            }
+            let matches = waiting.len().div_ceil(MAX_PLAYERS);
+            for players in waiting.chunks(waiting.len().div_ceil(matches)) {
+                if players.len() < 2 {
+                    continue;
+                }
+                for player in players {
+                    matchmaker.waiting_players.remove(player);
+                }
+                matchmaker.start_game(players.to_vec(), ctx);
             }
         });
     }
@@ -238,6 +615,7 @@ impl Handler<Join> for Matchmaker {
     type Result = ();
 
     fn handle(&mut self, Join(player): Join, _ctx: &mut Self::Context) {
+        self.withdraw(&player);
         self.waiting_players.insert(player);
     }
 }
@@ -250,12 +628,146 @@ impl Handler<Leave> for Matchmaker {
     type Result = ();
 
     fn handle(&mut self, Leave(player): Leave, _ctx: &mut Self::Context) {
-        self.waiting_players.remove(&player);
+        self.withdraw(&player);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct CreateRoom(Addr<Player>);
+
+impl Handler<CreateRoom> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, CreateRoom(player): CreateRoom, _ctx: &mut Self::Context) {
+        self.withdraw(&player);
+        let code = self.new_code();
+        let room = Room::new(player);
+        room.broadcast(&code);
+        self.rooms.insert(code, room);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct JoinRoom {
+    player: Addr<Player>,
+    code: String,
+}
+
+impl Handler<JoinRoom> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, msg: JoinRoom, _ctx: &mut Self::Context) {
+        let JoinRoom { player, code } = msg;
+        let code = code.trim().to_uppercase();
+        self.withdraw(&player);
+        match self.rooms.get_mut(&code) {
+            Some(room) if !room.is_full() => {
+                room.join(player);
+                room.broadcast(&code);
+            }
+            _ => player.do_send(Response(ResponseMessage::RoomUnavailable { code })),
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Ready(Addr<Player>);
+
+impl Handler<Ready> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, Ready(player): Ready, ctx: &mut Self::Context) {
+        let code = self
+            .rooms
+            .iter_mut()
+            .find_map(|(code, room)| room.set_ready(&player).then(|| code.clone()));
+        let Some(code) = code else {
+            return;
+        };
+
+        let room = &self.rooms[&code];
+        room.broadcast(&code);
+        if room.is_ready() {
+            let room = self.rooms.remove(&code).unwrap();
+            self.start_game(room.into_players(), ctx);
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct EndGame(Addr<Game>);
+
+impl Handler<EndGame> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, EndGame(game): EndGame, _ctx: &mut Self::Context) {
+        self.games.retain(|(_, x)| *x != game);
+        self.sessions.retain(|_, x| *x != game);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct ListGames(Addr<Player>);
+
+impl Handler<ListGames> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, ListGames(player): ListGames, _ctx: &mut Self::Context) {
+        let games = self.games.iter().map(|(info, _)| info.clone()).collect();
+        player.do_send(Response(ResponseMessage::Games { games }));
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Spectate {
+    player: Addr<Player>,
+    game: usize,
+}
+
+impl Handler<Spectate> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, msg: Spectate, ctx: &mut Self::Context) {
+        let Spectate { player, game } = msg;
+        self.withdraw(&player);
+        match self.games.iter().find(|(info, _)| info.id == game) {
+            Some((_, game)) => game.do_send(Subscribe(player)),
+            // The match ended in the meantime, so the spectator gets a fresh list instead.
+            None => self.handle(ListGames(player), ctx),
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Resume {
+    player: Addr<Player>,
+    session: String,
+}
+
+impl Handler<Resume> for Matchmaker {
+    type Result = ();
+
+    fn handle(&mut self, msg: Resume, _ctx: &mut Self::Context) {
+        let Resume { player, session } = msg;
+        self.withdraw(&player);
+        match self.sessions.get(&session) {
+            Some(game) => game.do_send(Reattach { player, session }),
+            None => player.do_send(Response(ResponseMessage::SessionExpired)),
+        }
     }
 }
 
 pub struct Player {
     game: Option<Addr<Game>>,
+    /// The match the player is watching.
+    spectating: Option<Addr<Game>>,
     matchmaker: Addr<Matchmaker>,
 }
 
@@ -264,6 +776,17 @@ impl Player {
         Player {
             matchmaker,
             game: None,
+            spectating: None,
+        }
+    }
+
+    /// Leaves the match the player is playing or watching.
+    fn quit_game(&mut self, ctx: &mut <Self as Actor>::Context) {
+        if let Some(game) = self.game.take() {
+            game.do_send(Quit(ctx.address()));
+        }
+        if let Some(game) = self.spectating.take() {
+            game.do_send(Unsubscribe(ctx.address()));
         }
     }
 }
@@ -271,21 +794,47 @@ impl Player {
 impl Actor for Player {
     type Context = ws::WebsocketContext<Self>;
 
+    /// A dropped connection keeps the player's board for them to resume, unlike leaving.
     fn stopped(&mut self, ctx: &mut Self::Context) {
+        if let Some(game) = self.game.take() {
+            game.do_send(Disconnect(ctx.address()));
+        }
+        self.quit_game(ctx);
         self.matchmaker.do_send(Leave(ctx.address()));
     }
 }
 
 #[derive(Message)]
 #[rtype(result = "()")]
-struct JoinGame(Addr<Game>);
+struct JoinGame {
+    game: Addr<Game>,
+    player: usize,
+    players: usize,
+    width: usize,
+    height: usize,
+    session: String,
+}
 
 impl Handler<JoinGame> for Player {
     type Result = ();
 
     fn handle(&mut self, msg: JoinGame, ctx: &mut Self::Context) {
-        let JoinGame(game) = msg;
-        if let Ok(json) = serde_json::to_string(&ResponseMessage::Ready) {
+        let JoinGame {
+            game,
+            player,
+            players,
+            width,
+            height,
+            session,
+        } = msg;
+        let ready = ResponseMessage::Ready {
+            player,
+            players,
+            width,
+            height,
+            session,
+        };
+        if let Ok(json) = serde_json::to_string(&ready) {
             ctx.text(json);
         }
 
@@ -293,15 +842,47 @@ impl Handler<JoinGame> for Player {
     }
 }
 
+/// Lets go of a match. Only the match it names is let go of, since the player may already be in
+/// another one by the time it arrives.
 #[derive(Message)]
 #[rtype(result = "()")]
-struct LeaveGame;
+struct LeaveGame(Addr<Game>);
 
 impl Handler<LeaveGame> for Player {
     type Result = ();
 
-    fn handle(&mut self, _msg: LeaveGame, _ctx: &mut Self::Context) {
-        self.game = None;
+    fn handle(&mut self, LeaveGame(game): LeaveGame, _ctx: &mut Self::Context) {
+        if self.game.as_ref() == Some(&game) {
+            self.game = None;
+        }
+        if self.spectating.as_ref() == Some(&game) {
+            self.spectating = None;
+        }
+    }
+}
+
+/// Puts a resumed player back in the match they were playing.
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Rejoin(Addr<Game>);
+
+impl Handler<Rejoin> for Player {
+    type Result = ();
+
+    fn handle(&mut self, Rejoin(game): Rejoin, _ctx: &mut Self::Context) {
+        self.game = Some(game);
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Watch(Addr<Game>);
+
+impl Handler<Watch> for Player {
+    type Result = ();
+
+    fn handle(&mut self, Watch(game): Watch, _ctx: &mut Self::Context) {
+        self.spectating = Some(game);
     }
 }
 
@@ -318,8 +899,49 @@ impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Player {
                     return;
                 };
                 match msg {
-                    RequestMessage::Join => self.matchmaker.do_send(Join(ctx.address())),
-                    RequestMessage::Leave => self.matchmaker.do_send(Leave(ctx.address())),
+                    RequestMessage::Join => {
+                        self.quit_game(ctx);
+                        self.matchmaker.do_send(Join(ctx.address()));
+                    }
+                    RequestMessage::Leave => {
+                        self.quit_game(ctx);
+                        self.matchmaker.do_send(Leave(ctx.address()));
+                    }
+                    RequestMessage::CreateRoom => {
+                        self.quit_game(ctx);
+                        self.matchmaker.do_send(CreateRoom(ctx.address()));
+                    }
+                    RequestMessage::JoinRoom { code } => {
+                        self.quit_game(ctx);
+                        self.matchmaker.do_send(JoinRoom {
+                            player: ctx.address(),
+                            code,
+                        });
+                    }
+                    RequestMessage::Ready => self.matchmaker.do_send(Ready(ctx.address())),
+                    RequestMessage::Resume { session } => {
+                        self.quit_game(ctx);
+                        self.matchmaker.do_send(Resume {
+                            player: ctx.address(),
+                            session,
+                        });
+                    }
+                    RequestMessage::ListGames => self.matchmaker.do_send(ListGames(ctx.address())),
+                    RequestMessage::Spectate { game } => {
+                        self.quit_game(ctx);
+                        self.matchmaker.do_send(Spectate {
+                            player: ctx.address(),
+                            game,
+                        });
+                    }
+                    RequestMessage::Target { targeting } => {
+                        if let Some(game) = &self.game {
+                            game.do_send(Target {
+                                player: ctx.address(),
+                                targeting,
+                            });
+                        }
+                    }
                     RequestMessage::Remove { x, y } => {
                         if let Some(game) = &mut self.game {
                             let player = ctx.address();
@@ -1,9 +1,17 @@
 mod game;
+mod room;
+mod session;
+mod standings;
 
 use actix::prelude::*;
 use actix_web::{get, web, App, HttpRequest, HttpResponse, HttpServer, Responder};
 use actix_web_actors::ws;
+use common::replay::Rules;
 use game::*;
+use std::time::Duration;
+
+/// Matches nobody has won after this long are a draw between the players still in.
+const MATCH_TIME_LIMIT: Duration = Duration::from_secs(5 * 60);
 
 #[get("/ws")]
 async fn websocket(
@@ -21,7 +29,7 @@ async fn hello() -> impl Responder {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let matchmaker = Matchmaker::new().start();
+    let matchmaker = Matchmaker::new(Rules::default(), Some(MATCH_TIME_LIMIT)).start();
     HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(matchmaker.clone()))
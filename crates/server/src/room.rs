@@ -0,0 +1,116 @@
+//! Private rooms, which players join by code instead of queueing with everyone. The match starts
+//! once everyone in the room is ready.
+
+/// Players in a room at most, as many as a match from the queue.
+pub const ROOM_CAPACITY: usize = 8;
+
+/// Players who joined a private room by its code, each with whether they are ready.
+#[derive(Clone, Debug)]
+pub struct Room<P> {
+    players: Vec<(P, bool)>,
+}
+
+impl<P: PartialEq> Room<P> {
+    /// A room with only the player who opened it.
+    pub fn new(host: P) -> Self {
+        Room {
+            players: vec![(host, false)],
+        }
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &P> {
+        self.players.iter().map(|(player, _)| player)
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    pub fn ready(&self) -> usize {
+        self.players.iter().filter(|&&(_, ready)| ready).count()
+    }
+
+    pub fn contains(&self, player: &P) -> bool {
+        self.players().any(|member| member == player)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.players.len() >= ROOM_CAPACITY
+    }
+
+    /// Lets the player in. Whether the room is full is up to the caller to check.
+    pub fn join(&mut self, player: P) {
+        self.players.push((player, false));
+    }
+
+    pub fn leave(&mut self, player: &P) {
+        self.players.retain(|(member, _)| member != player);
+    }
+
+    /// Marks the player ready, returning whether they are in the room.
+    pub fn set_ready(&mut self, player: &P) -> bool {
+        match self.players.iter_mut().find(|(member, _)| member == player) {
+            Some((_, ready)) => {
+                *ready = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Everyone is ready, and there is someone to play against.
+    pub fn is_ready(&self) -> bool {
+        self.players.len() >= 2 && self.players.iter().all(|&(_, ready)| ready)
+    }
+
+    pub fn into_players(self) -> Vec<P> {
+        self.players.into_iter().map(|(player, _)| player).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ready() {
+        let mut room = Room::new(0);
+        assert!(room.set_ready(&0));
+        assert!(!room.is_ready());
+
+        room.join(1);
+        assert!(!room.is_ready());
+        assert!(!room.set_ready(&2));
+        assert!(room.set_ready(&1));
+        assert_eq!(room.ready(), 2);
+        assert!(room.is_ready());
+
+        // Someone new has to be ready too.
+        room.join(2);
+        assert!(!room.is_ready());
+        room.leave(&2);
+        assert!(room.is_ready());
+        assert_eq!(room.into_players(), [0, 1]);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut room = Room::new(0);
+        for player in 1..ROOM_CAPACITY {
+            assert!(!room.is_full());
+            room.join(player);
+        }
+        assert!(room.is_full());
+        assert_eq!(room.len(), ROOM_CAPACITY);
+
+        for player in 0..ROOM_CAPACITY {
+            room.leave(&player);
+        }
+        assert!(room.is_empty());
+        assert!(!room.contains(&0));
+    }
+}
@@ -0,0 +1,105 @@
+//! Session tokens that let a player take their board back after losing their connection, as long
+//! as they come back within the grace period.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a disconnected player's board waits for them to resume their session.
+pub const RECONNECT_GRACE: Duration = Duration::from_secs(30);
+
+/// The session token of every player still in a match, and when the disconnected ones lost their
+/// connection.
+#[derive(Clone, Debug, Default)]
+pub struct Sessions {
+    tokens: HashMap<String, usize>,
+    disconnected: HashMap<usize, Instant>,
+}
+
+impl Sessions {
+    /// Players are numbered in the order of their tokens.
+    pub fn new(tokens: Vec<String>) -> Self {
+        Sessions {
+            tokens: tokens
+                .into_iter()
+                .enumerate()
+                .map(|(id, x)| (x, id))
+                .collect(),
+            disconnected: HashMap::new(),
+        }
+    }
+
+    pub fn disconnect(&mut self, player: usize, now: Instant) {
+        self.disconnected.insert(player, now);
+    }
+
+    /// The player `token` belongs to, if their board is still waiting for them at `now`. A
+    /// player who never disconnected may resume too, taking the board from their old connection.
+    pub fn resume(&mut self, token: &str, now: Instant) -> Option<usize> {
+        let player = *self.tokens.get(token)?;
+        match self.disconnected.get(&player) {
+            Some(&since) if now.duration_since(since) >= RECONNECT_GRACE => None,
+            _ => {
+                self.disconnected.remove(&player);
+                Some(player)
+            }
+        }
+    }
+
+    /// Players who have been disconnected for the whole grace period by `now`.
+    pub fn expired(&self, now: Instant) -> Vec<usize> {
+        let mut players: Vec<_> = self
+            .disconnected
+            .iter()
+            .filter(|(_, &since)| now.duration_since(since) >= RECONNECT_GRACE)
+            .map(|(&player, _)| player)
+            .collect();
+        players.sort_unstable();
+        players
+    }
+
+    /// Forgets the token of a player who left the match, so it can no longer be resumed.
+    pub fn forget(&mut self, player: usize) {
+        self.tokens.retain(|_, &mut id| id != player);
+        self.disconnected.remove(&player);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sessions() -> Sessions {
+        Sessions::new(vec!["a".to_string(), "b".to_string()])
+    }
+
+    #[test]
+    fn test_resume() {
+        let start = Instant::now();
+        let mut sessions = sessions();
+        assert_eq!(sessions.resume("b", start), Some(1));
+        assert_eq!(sessions.resume("c", start), None);
+
+        sessions.disconnect(0, start);
+        let later = start + RECONNECT_GRACE / 2;
+        assert!(sessions.expired(later).is_empty());
+        assert_eq!(sessions.resume("a", later), Some(0));
+        assert!(sessions.expired(start + RECONNECT_GRACE).is_empty());
+    }
+
+    #[test]
+    fn test_expire() {
+        let start = Instant::now();
+        let mut sessions = sessions();
+        sessions.disconnect(1, start);
+        sessions.disconnect(0, start + RECONNECT_GRACE / 2);
+
+        let end = start + RECONNECT_GRACE;
+        assert_eq!(sessions.expired(end), [1]);
+        assert_eq!(sessions.resume("b", end), None);
+        assert_eq!(sessions.resume("a", end), Some(0));
+
+        sessions.forget(1);
+        assert!(sessions.expired(end).is_empty());
+        assert_eq!(sessions.resume("b", start), None);
+    }
+}
@@ -0,0 +1,90 @@
+//! Where the players of a match finish. Players drop out one at a time, by topping out or by
+//! leaving, and each is placed behind everyone still in. The last one in wins.
+
+/// The place of every player in a match, by player number. Players without a place are still in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Standings {
+    places: Vec<Option<usize>>,
+}
+
+impl Standings {
+    pub fn new(players: usize) -> Self {
+        Standings {
+            places: vec![None; players],
+        }
+    }
+
+    pub fn place(&self, player: usize) -> Option<usize> {
+        self.places[player]
+    }
+
+    pub fn is_in(&self, player: usize) -> bool {
+        self.places[player].is_none()
+    }
+
+    pub fn alive(&self) -> usize {
+        self.places.iter().filter(|place| place.is_none()).count()
+    }
+
+    /// Places the player behind everyone still in, returning the place. A player who is already
+    /// out keeps their place and `None` is returned.
+    pub fn eliminate(&mut self, player: usize) -> Option<usize> {
+        if !self.is_in(player) {
+            return None;
+        }
+        let place = self.alive();
+        self.places[player] = Some(place);
+        Some(place)
+    }
+
+    /// The match is over once at most one player is still in.
+    pub fn is_over(&self) -> bool {
+        self.alive() <= 1
+    }
+
+    /// Places the last player still in first, returning who they are. Nobody wins while more than
+    /// one player is in.
+    pub fn crown(&mut self) -> Option<usize> {
+        if self.alive() != 1 {
+            return None;
+        }
+        let winner = self.places.iter().position(Option::is_none)?;
+        self.places[winner] = Some(1);
+        Some(winner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_placement() {
+        let mut standings = Standings::new(4);
+        assert_eq!(standings.alive(), 4);
+        assert_eq!(standings.crown(), None);
+
+        assert_eq!(standings.eliminate(2), Some(4));
+        assert_eq!(standings.eliminate(2), None);
+        assert_eq!(standings.place(2), Some(4));
+        assert_eq!(standings.eliminate(0), Some(3));
+        assert!(!standings.is_over());
+
+        assert_eq!(standings.eliminate(3), Some(2));
+        assert!(standings.is_over());
+        assert_eq!(standings.crown(), Some(1));
+        assert_eq!(standings.place(1), Some(1));
+        assert!(!standings.is_in(1));
+        assert_eq!(standings.crown(), None);
+    }
+
+    #[test]
+    fn test_win() {
+        let mut standings = Standings::new(2);
+        assert!(!standings.is_over());
+        assert_eq!(standings.eliminate(0), Some(2));
+        assert!(standings.is_over());
+        assert_eq!(standings.crown(), Some(1));
+        assert_eq!(standings.alive(), 0);
+    }
+}